@@ -23,10 +23,10 @@ pub fn main() {
     let playback_decoder = decoder.clone();
     config.set_data_callback(move |_device, output, _frames| {
         if !playback_rewind.load(Ordering::Acquire) {
-            let frames = playback_decoder.read_pcm_frames(output);
+            let result = playback_decoder.read_pcm_frames_with_status(output);
 
-            // If there were no more frames read, request a rewind.
-            if frames == 0 {
+            // If the decoder ran out of frames, request a rewind.
+            if result.end_of_stream {
                 playback_rewind.store(true, Ordering::Release);
             }
         }
@@ -0,0 +1,66 @@
+use miniaudio::{Backend, Context, DeviceConfig, DeviceState, DeviceType, Format};
+use miniaudio::{ReconnectEvent, ResilientDevice};
+use std::sync::mpsc::{self, Receiver};
+use std::time::Duration;
+
+fn wait_for_reconnect(event_receiver: &Receiver<ReconnectEvent>) {
+    loop {
+        match event_receiver.recv_timeout(Duration::from_secs(5)) {
+            Ok(ReconnectEvent::Reconnected { .. }) => return,
+            Ok(_) => {}
+            Err(_) => panic!("device was not reopened"),
+        }
+    }
+}
+
+pub fn main() {
+    // The null backend doesn't output anything but it behaves like a real device, which makes it
+    // easy to see what happens when a device stops unexpectedly.
+    let context = Context::new(&[Backend::Null], None).expect("failed to create null context");
+
+    let mut device_config = DeviceConfig::new(DeviceType::Playback);
+    device_config.playback_mut().set_format(Format::F32);
+    device_config.playback_mut().set_channels(2);
+    device_config.set_sample_rate(miniaudio::SAMPLE_RATE_48000);
+
+    let device = ResilientDevice::new(Some(context), device_config, |_device, output, _input| {
+        output.as_bytes_mut().iter_mut().for_each(|b| *b = 0);
+    })
+    .expect("failed to open playback device");
+
+    let (event_sender, event_receiver) = mpsc::channel();
+    device.set_event_callback(move |event| {
+        println!("Event: {:?}", event);
+        let _ = event_sender.send(event);
+    });
+
+    device.start().expect("failed to start device");
+
+    // Stopping the device behind the back of the resilient device counts as an unexpected stop.
+    println!("Forcing the device to stop...");
+    device
+        .device()
+        .expect("device is not open")
+        .stop()
+        .expect("failed to stop device");
+
+    wait_for_reconnect(&event_receiver);
+
+    // Stopping the device with `stop` marks it as `Stopping` before the stop callback is called,
+    // but when the backend stops a device it is still `Started` until the stop callback returns.
+    // The old device must not be closed before miniaudio has finished stopping it.
+    println!("Simulating a stop by the backend...");
+    let old_device = device.device().expect("device is not open");
+    device.simulate_backend_stop(Duration::from_millis(200));
+    wait_for_reconnect(&event_receiver);
+    assert_eq!(
+        old_device.state(),
+        DeviceState::Stopped,
+        "device was reopened before the old device stopped"
+    );
+    drop(old_device);
+
+    let reopened = device.device().expect("device is not open");
+    println!("Device reopened. Started: {}", reopened.is_started());
+    println!("Shutting Down...");
+}
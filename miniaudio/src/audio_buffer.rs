@@ -0,0 +1,340 @@
+use crate::base::*;
+use crate::frames::{Frames, FramesMut, Sample};
+use miniaudio_sys as sys;
+use std::marker::PhantomData;
+use std::mem::MaybeUninit;
+use std::ops::{Deref, DerefMut};
+
+#[repr(transparent)]
+pub struct RawAudioBuffer {
+    inner: sys::ma_audio_buffer,
+}
+
+impl RawAudioBuffer {
+    /// Reads frames from the current cursor into `output`, returning the number of frames that
+    /// were read. If `looping` is true the cursor will wrap back around to the start of the buffer
+    /// once it reaches the end and `output` will always be filled completely.
+    #[inline]
+    pub fn read_pcm_frames(&mut self, output: &mut FramesMut, looping: bool) -> u64 {
+        assert!(
+            output.format() == self.format(),
+            "output and audio buffer format did not match (output: {:?}, input: {:?})",
+            output.format(),
+            self.format()
+        );
+
+        assert!(
+            output.channels() == self.channels(),
+            "output and audio buffer channel count did not match (output: {}, input: {})",
+            output.channels(),
+            self.channels()
+        );
+
+        unsafe {
+            sys::ma_audio_buffer_read_pcm_frames(
+                &mut self.inner,
+                output.as_mut_ptr() as *mut _,
+                output.frame_count() as u64,
+                to_bool32(looping),
+            )
+        }
+    }
+
+    #[inline]
+    pub fn seek_to_pcm_frame(&mut self, frame_index: u64) -> Result<(), Error> {
+        Error::from_c_result(unsafe {
+            sys::ma_audio_buffer_seek_to_pcm_frame(&mut self.inner, frame_index)
+        })
+    }
+
+    /// Maps up to `frame_count` frames starting at the current cursor for reading without copying
+    /// them. The mapped frames may be fewer than requested if the end of the buffer is reached.
+    /// The cursor is advanced when the returned mapping is unmapped or dropped.
+    pub fn map(&mut self, frame_count: u64) -> Result<AudioBufferMapping<'_>, Error> {
+        let mut mapped_count = frame_count;
+        let mut frames_ptr: *mut std::ffi::c_void = std::ptr::null_mut();
+
+        let result = unsafe {
+            sys::ma_audio_buffer_map(&mut self.inner, &mut frames_ptr, &mut mapped_count)
+        };
+
+        map_result!(
+            result,
+            AudioBufferMapping {
+                inner: self,
+                ptr: frames_ptr as *const u8,
+                frame_count: mapped_count as usize,
+            }
+        )
+    }
+
+    /// Returns true if the cursor is at the end of the buffer.
+    #[inline]
+    pub fn at_end(&self) -> bool {
+        // NOTE: This returns a boolean in miniaudio even though it is declared as an ma_result.
+        unsafe { sys::ma_audio_buffer_at_end(&self.inner as *const _ as *mut _) != 0 }
+    }
+
+    /// Returns the number of frames between the cursor and the end of the buffer.
+    #[inline]
+    pub fn available_frames(&self) -> u64 {
+        let mut available = 0;
+        let result = unsafe {
+            sys::ma_audio_buffer_get_available_frames(
+                &self.inner as *const _ as *mut _,
+                &mut available,
+            )
+        };
+
+        // This shouldn't fail because our arguments are valid, but we debug assert just to be sure.
+        debug_assert!(result == 0);
+
+        available
+    }
+
+    /// The index of the next frame that will be read.
+    #[inline]
+    pub fn cursor(&self) -> u64 {
+        self.inner.cursor
+    }
+
+    #[inline]
+    pub fn length_in_pcm_frames(&self) -> u64 {
+        self.inner.sizeInFrames
+    }
+
+    #[inline]
+    pub fn format(&self) -> Format {
+        Format::from_c(self.inner.format)
+    }
+
+    #[inline]
+    pub fn channels(&self) -> u32 {
+        self.inner.channels
+    }
+
+    /// Initializes an audio buffer over `frame_count` frames at `data`. If `copy` is true the data
+    /// will be copied into memory owned by the audio buffer.
+    fn alloc(
+        format: Format,
+        channels: u32,
+        frame_count: u64,
+        data: *const u8,
+        copy: bool,
+    ) -> Result<Box<RawAudioBuffer>, Error> {
+        let buffer = Box::new(MaybeUninit::<RawAudioBuffer>::uninit());
+
+        let result = unsafe {
+            let config = sys::ma_audio_buffer_config_init(
+                format as _,
+                channels,
+                frame_count,
+                data as *const _,
+                std::ptr::null(),
+            );
+
+            if copy {
+                sys::ma_audio_buffer_init_copy(&config, buffer.as_ptr() as *mut _)
+            } else {
+                sys::ma_audio_buffer_init(&config, buffer.as_ptr() as *mut _)
+            }
+        };
+
+        map_result!(result, unsafe {
+            std::mem::transmute::<Box<MaybeUninit<RawAudioBuffer>>, Box<RawAudioBuffer>>(buffer)
+        })
+    }
+}
+
+impl Drop for RawAudioBuffer {
+    fn drop(&mut self) {
+        unsafe { sys::ma_audio_buffer_uninit(&mut self.inner) };
+    }
+}
+
+/// An audio buffer that owns the frames that it reads from.
+pub struct AudioBuffer {
+    inner: Box<RawAudioBuffer>,
+
+    // NOTE: This must be declared after `inner` so that the samples outlive the audio buffer that
+    // is reading from them.
+    _samples: Option<Box<dyn Send + Sync>>,
+}
+
+impl AudioBuffer {
+    /// Creates an audio buffer from a copy of the given frames.
+    pub fn from_frames(frames: &Frames) -> Result<AudioBuffer, Error> {
+        RawAudioBuffer::alloc(
+            frames.format(),
+            frames.channels(),
+            frames.frame_count() as u64,
+            frames.as_ptr(),
+            true,
+        )
+        .map(|inner| AudioBuffer {
+            inner,
+            _samples: None,
+        })
+    }
+
+    /// Creates an audio buffer that takes ownership of a vector of interleaved samples without
+    /// copying them. The format of the buffer is taken from the sample type.
+    pub fn from_vec<S: 'static + Sample + Send + Sync>(
+        samples: Vec<S>,
+        channels: u32,
+    ) -> Result<AudioBuffer, Error> {
+        if channels == 0 {
+            return Err(Error::InvalidArgs);
+        }
+
+        let samples = samples.into_boxed_slice();
+        let frame_count = (samples.len() / channels as usize) as u64;
+        let inner = RawAudioBuffer::alloc(
+            S::format(),
+            channels,
+            frame_count,
+            samples.as_ptr() as *const u8,
+            false,
+        )?;
+
+        Ok(AudioBuffer {
+            inner,
+            _samples: Some(Box::new(samples)),
+        })
+    }
+}
+
+impl Deref for AudioBuffer {
+    type Target = RawAudioBuffer;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl DerefMut for AudioBuffer {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.inner
+    }
+}
+
+unsafe impl Send for AudioBuffer {}
+unsafe impl Sync for AudioBuffer {}
+
+/// An audio buffer that reads from frames that it borrows.
+pub struct AudioBufferRef<'a> {
+    inner: Box<RawAudioBuffer>,
+    _data: PhantomData<&'a [u8]>,
+}
+
+impl<'a> AudioBufferRef<'a> {
+    /// Creates an audio buffer that reads directly from the given frames without copying them.
+    pub fn from_frames(frames: &Frames<'a>) -> Result<AudioBufferRef<'a>, Error> {
+        RawAudioBuffer::alloc(
+            frames.format(),
+            frames.channels(),
+            frames.frame_count() as u64,
+            frames.as_ptr(),
+            false,
+        )
+        .map(|inner| AudioBufferRef {
+            inner,
+            _data: PhantomData,
+        })
+    }
+
+    /// Creates an audio buffer that reads directly from a slice of interleaved samples without
+    /// copying them. The format of the buffer is taken from the sample type.
+    pub fn from_samples<S: Sample>(
+        samples: &'a [S],
+        channels: u32,
+    ) -> Result<AudioBufferRef<'a>, Error> {
+        Self::from_frames(&Frames::wrap(samples, S::format(), channels))
+    }
+}
+
+impl<'a> Deref for AudioBufferRef<'a> {
+    type Target = RawAudioBuffer;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl<'a> DerefMut for AudioBufferRef<'a> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.inner
+    }
+}
+
+unsafe impl<'a> Send for AudioBufferRef<'a> {}
+unsafe impl<'a> Sync for AudioBufferRef<'a> {}
+
+/// Frames that have been mapped directly from an audio buffer. Unless `unmap_count` is used, all of
+/// the mapped frames are consumed and the buffer's cursor is advanced past them when this is
+/// unmapped or dropped.
+pub struct AudioBufferMapping<'a> {
+    inner: &'a mut RawAudioBuffer,
+    ptr: *const u8,
+    frame_count: usize,
+}
+
+impl<'a> AudioBufferMapping<'a> {
+    /// The mapped frames.
+    pub fn frames(&self) -> Frames<'_> {
+        let byte_count =
+            self.frame_count * self.inner.format().size_in_bytes() * self.inner.channels() as usize;
+
+        let data: &[u8] = if self.ptr.is_null() || byte_count == 0 {
+            &[]
+        } else {
+            unsafe { std::slice::from_raw_parts(self.ptr, byte_count) }
+        };
+
+        Frames::wrap(data, self.inner.format(), self.inner.channels())
+    }
+
+    /// The number of frames that were mapped.
+    pub fn frame_count(&self) -> usize {
+        self.frame_count
+    }
+
+    /// Unmaps the frames, advancing the cursor past all of them.
+    pub fn unmap(self) -> Result<(), Error> {
+        let count = self.frame_count;
+        self.unmap_count(count)
+    }
+
+    /// Unmaps the frames, advancing the cursor past only the first `count` mapped frames.
+    pub fn unmap_count(mut self, count: usize) -> Result<(), Error> {
+        assert!(
+            count <= self.frame_count,
+            "unmapped more frames than were mapped (unmapped: {}, mapped: {})",
+            count,
+            self.frame_count
+        );
+
+        let result = self.unmap_internal(count);
+        std::mem::forget(self);
+        result
+    }
+
+    fn unmap_internal(&mut self, count: usize) -> Result<(), Error> {
+        let result = unsafe { sys::ma_audio_buffer_unmap(&mut self.inner.inner, count as u64) };
+
+        // Reaching the end of the buffer is reported as MA_AT_END but it is not an error here.
+        if result == sys::MA_AT_END {
+            Ok(())
+        } else {
+            Error::from_c_result(result)
+        }
+    }
+}
+
+impl<'a> Drop for AudioBufferMapping<'a> {
+    fn drop(&mut self) {
+        let count = self.frame_count;
+        let result = self.unmap_internal(count);
+        debug_assert!(result.is_ok());
+    }
+}
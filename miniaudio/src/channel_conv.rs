@@ -1,5 +1,5 @@
 use crate::base::{from_bool8, Channel, ChannelMixMode, Error, Format, MAX_CHANNELS};
-use crate::frames::{Frames, FramesMut};
+use crate::frames::{process_in_place_chunked, Frames, FramesMut};
 use miniaudio_sys as sys;
 
 /// Configuration for `ChannelConverter`.
@@ -7,6 +7,7 @@ use miniaudio_sys as sys;
 pub struct ChannelConverterConfig(sys::ma_channel_converter_config);
 
 impl ChannelConverterConfig {
+    /// A `ChannelMap` can be passed directly as either of the channel maps.
     pub fn new(
         format: Format,
         channel_map_in: &[Channel],
@@ -234,6 +235,37 @@ impl ChannelConverter {
             )
         })
     }
+
+    /// Converts `frames` in place. This is only possible when the converter has the same number of
+    /// input and output channels (e.g. when it is used to reorder or remix channels) and will fail
+    /// with `InvalidArgs` otherwise. This does not allocate.
+    pub fn process_pcm_frames_in_place(&mut self, frames: &mut FramesMut) -> Result<(), Error> {
+        if self.channels_in() != self.channels_out() {
+            ma_debug_panic!(
+                "in place channel conversion with different channel counts (in: {}, out: {})",
+                self.channels_in(),
+                self.channels_out()
+            );
+            return Err(Error::InvalidArgs);
+        }
+
+        if frames.format() != self.format() || frames.channels() != self.channels_in() {
+            ma_debug_panic!(
+                "frames did not match the converter (frames: {:?}/{}, converter: {:?}/{})",
+                frames.format(),
+                frames.channels(),
+                self.format(),
+                self.channels_in()
+            );
+            return Err(Error::InvalidArgs);
+        }
+
+        // NOTE: miniaudio reads the input frame while writing the output frame when shuffling or
+        // mixing channels, so the output and input can't point to the same frames.
+        process_in_place_chunked(frames, |output, input| {
+            self.process_pcm_frames(output, input)
+        })
+    }
 }
 
 impl Drop for ChannelConverter {
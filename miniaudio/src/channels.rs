@@ -1,14 +1,161 @@
-use crate::base::{Channel, ChannelMixMode};
+use crate::base::{from_bool32, Channel, StandardChannelMap, MAX_CHANNELS};
+use miniaudio_sys as sys;
+use std::ops::{Deref, DerefMut};
 
-pub struct ChannelMap<'m> {
-    mixing_mode: ChannelMixMode,
-    map_in: &'m [Channel],
-    map_out: &'m [Channel],
+/// The position of every channel in a stream of up to `MAX_CHANNELS` channels.
+#[derive(Clone, Copy)]
+pub struct ChannelMap {
+    channels: u32,
+    map: [Channel; MAX_CHANNELS],
 }
 
-impl<'m> ChannelMap<'m> {
-    pub fn set(index: usize, channel_in: Channel, channel_out: Channel) {
+impl ChannelMap {
+    /// Creates a channel map where every position is `Channel::None`. A blank channel map tells
+    /// miniaudio to use its default channel map instead.
+    ///
+    /// This will panic if `channels` is greater than `MAX_CHANNELS`.
+    pub fn blank(channels: u32) -> ChannelMap {
+        let mut map = ChannelMap::uninit(channels);
+        unsafe { sys::ma_channel_map_init_blank(channels, map.as_mut_ptr()) };
+        map
+    }
+
+    /// Creates a channel map using one of the standard channel maps.
+    ///
+    /// This will panic if `channels` is greater than `MAX_CHANNELS`.
+    pub fn standard(standard: StandardChannelMap, channels: u32) -> ChannelMap {
+        let mut map = ChannelMap::uninit(channels);
+        unsafe { sys::ma_get_standard_channel_map(standard as _, channels, map.as_mut_ptr()) };
+        map
+    }
+
+    /// Creates a channel map from a list of channel positions.
+    ///
+    /// This will panic if there are more than `MAX_CHANNELS` channels.
+    pub fn from_channels(channels: &[Channel]) -> ChannelMap {
+        let mut map = ChannelMap::uninit(channels.len() as u32);
+        map.map[..channels.len()].copy_from_slice(channels);
+        map
+    }
+
+    /// Creates a channel map from a list of channel positions, or the default channel map for the
+    /// number of channels if `channels` is blank.
+    ///
+    /// This will panic if there are more than `MAX_CHANNELS` channels.
+    pub fn from_channels_or_default(channels: &[Channel]) -> ChannelMap {
+        let mut map = ChannelMap::uninit(channels.len() as u32);
+        unsafe {
+            sys::ma_channel_map_copy_or_default(
+                map.as_mut_ptr(),
+                channels.as_ptr().cast(),
+                channels.len() as u32,
+            )
+        };
+        map
+    }
+
+    /// Returns the number of channels in this channel map.
+    #[inline]
+    pub fn channels(&self) -> u32 {
+        self.channels
+    }
+
+    #[inline]
+    pub fn as_slice(&self) -> &[Channel] {
+        &self.map[..self.channels as usize]
+    }
+
+    #[inline]
+    pub fn as_mut_slice(&mut self) -> &mut [Channel] {
+        &mut self.map[..self.channels as usize]
+    }
+
+    /// Returns true if this channel map is valid. A channel map is invalid if it has no channels
+    /// or if it has more than one channel and contains `Channel::Mono`.
+    ///
+    /// NOTE: This does not check for positions that are used more than once.
+    pub fn is_valid(&self) -> bool {
+        from_bool32(unsafe { sys::ma_channel_map_valid(self.channels, self.as_ptr()) })
+    }
+
+    /// Returns true if every position in this channel map is `Channel::None`.
+    pub fn is_blank(&self) -> bool {
+        from_bool32(unsafe { sys::ma_channel_map_blank(self.channels, self.as_ptr()) })
+    }
+
+    /// Returns true if `channel` is one of the positions in this channel map.
+    pub fn contains(&self, channel: Channel) -> bool {
+        from_bool32(unsafe {
+            sys::ma_channel_map_contains_channel_position(
+                self.channels,
+                self.as_ptr(),
+                channel as _,
+            )
+        })
+    }
+
+    pub(crate) fn as_ptr(&self) -> *const sys::ma_channel {
+        self.map.as_ptr().cast()
+    }
+
+    pub(crate) fn as_mut_ptr(&mut self) -> *mut sys::ma_channel {
+        self.map.as_mut_ptr().cast()
+    }
+
+    /// Copies this channel map into a raw channel map array like the ones found in configs.
+    pub(crate) fn copy_to_raw(&self, dest: &mut [sys::ma_channel; MAX_CHANNELS]) {
+        unsafe { sys::ma_channel_map_copy(dest.as_mut_ptr(), self.as_ptr(), self.channels) };
+    }
+
+    /// Reads the first `channels` positions of a raw channel map array.
+    pub(crate) fn from_raw(raw: &[sys::ma_channel; MAX_CHANNELS], channels: u32) -> ChannelMap {
+        let mut map = ChannelMap::uninit(channels);
+        unsafe { sys::ma_channel_map_copy(map.as_mut_ptr(), raw.as_ptr(), channels) };
+        map
+    }
+
+    fn uninit(channels: u32) -> ChannelMap {
+        assert!(
+            channels as usize <= MAX_CHANNELS,
+            "too many channels for a channel map (channels: {}, max: {})",
+            channels,
+            MAX_CHANNELS
+        );
+
+        ChannelMap {
+            channels,
+            map: [Channel::None; MAX_CHANNELS],
+        }
     }
 }
 
-pub struct ChannelMapIn()
+impl Deref for ChannelMap {
+    type Target = [Channel];
+
+    fn deref(&self) -> &Self::Target {
+        self.as_slice()
+    }
+}
+
+impl DerefMut for ChannelMap {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.as_mut_slice()
+    }
+}
+
+impl PartialEq for ChannelMap {
+    fn eq(&self, other: &ChannelMap) -> bool {
+        self.channels == other.channels
+            && from_bool32(unsafe {
+                sys::ma_channel_map_equal(self.channels, self.as_ptr(), other.as_ptr())
+            })
+    }
+}
+
+impl Eq for ChannelMap {}
+
+impl std::fmt::Debug for ChannelMap {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_list().entries(self.as_slice()).finish()
+    }
+}
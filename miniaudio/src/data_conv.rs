@@ -1,5 +1,6 @@
-use crate::base::{Error, Format};
-use crate::frames::{Frames, FramesMut};
+use crate::base::{from_bool8, Error, Format};
+use crate::channels::ChannelMap;
+use crate::frames::{process_in_place_chunked, Frames, FramesMut};
 use crate::resampling::ResampleAlgorithm;
 use miniaudio_sys as sys;
 
@@ -66,6 +67,34 @@ impl DataConverterConfig {
         self.0.channelsOut = channels;
     }
 
+    /// Returns the input channel map for the configured number of input channels.
+    #[inline]
+    pub fn channel_map_in(&self) -> ChannelMap {
+        ChannelMap::from_raw(&self.0.channelMapIn, self.0.channelsIn)
+    }
+
+    /// Sets the input channel map. This also sets the number of input channels to the number of
+    /// channels in the channel map.
+    #[inline]
+    pub fn set_channel_map_in(&mut self, channel_map: &ChannelMap) {
+        channel_map.copy_to_raw(&mut self.0.channelMapIn);
+        self.0.channelsIn = channel_map.channels();
+    }
+
+    /// Returns the output channel map for the configured number of output channels.
+    #[inline]
+    pub fn channel_map_out(&self) -> ChannelMap {
+        ChannelMap::from_raw(&self.0.channelMapOut, self.0.channelsOut)
+    }
+
+    /// Sets the output channel map. This also sets the number of output channels to the number of
+    /// channels in the channel map.
+    #[inline]
+    pub fn set_channel_map_out(&mut self, channel_map: &ChannelMap) {
+        channel_map.copy_to_raw(&mut self.0.channelMapOut);
+        self.0.channelsOut = channel_map.channels();
+    }
+
     pub fn sample_rate_in(&self) -> u32 {
         self.0.sampleRateIn
     }
@@ -133,6 +162,10 @@ impl DataConverter {
         }
     }
 
+    /// Converts the given input data.
+    ///
+    /// Returns the number of input frames that were consumed during processing and the number of
+    /// output frames that were written to the output buffer respectively.
     #[inline]
     pub fn process_pcm_frames(
         &mut self,
@@ -152,7 +185,40 @@ impl DataConverter {
             )
         })?;
 
-        Ok((output_frame_count, input_frame_count))
+        Ok((input_frame_count, output_frame_count))
+    }
+
+    /// Converts `frames` in place. This is only possible when the converter does not change the
+    /// format, channel count or sample rate of the frames (e.g. when it only remaps channels) and
+    /// will fail with `InvalidArgs` otherwise. This does not allocate.
+    pub fn process_pcm_frames_in_place(&mut self, frames: &mut FramesMut) -> Result<(), Error> {
+        let config = &self.0.config;
+        if config.formatIn != config.formatOut
+            || config.channelsIn != config.channelsOut
+            || from_bool8(self.0.hasResampler)
+        {
+            ma_debug_panic!("in place data conversion with a converter that changes the format, channel count or sample rate");
+            return Err(Error::InvalidArgs);
+        }
+
+        if frames.format() != Format::from_c(config.formatIn)
+            || frames.channels() != config.channelsIn
+        {
+            ma_debug_panic!(
+                "frames did not match the converter (frames: {:?}/{}, converter: {:?}/{})",
+                frames.format(),
+                frames.channels(),
+                Format::from_c(config.formatIn),
+                config.channelsIn
+            );
+            return Err(Error::InvalidArgs);
+        }
+
+        process_in_place_chunked(frames, |output, input| {
+            let (input_frame_count, output_frame_count) = self.process_pcm_frames(output, input)?;
+            debug_assert_eq!(output_frame_count, input_frame_count);
+            Ok(())
+        })
     }
 
     pub fn set_rate(&mut self, sample_rate_in: u32, sample_rate_out: u32) -> Result<(), Error> {
@@ -170,7 +236,7 @@ impl DataConverter {
     pub fn required_input_frame_count(&self, output_frame_count: u64) -> u64 {
         unsafe {
             sys::ma_data_converter_get_required_input_frame_count(
-                &self as *const _ as *mut _,
+                &self.0 as *const _ as *mut _,
                 output_frame_count,
             )
         }
@@ -179,18 +245,18 @@ impl DataConverter {
     pub fn expected_output_frame_count(&self, input_frame_count: u64) -> u64 {
         unsafe {
             sys::ma_data_converter_get_expected_output_frame_count(
-                &self as *const _ as *mut _,
+                &self.0 as *const _ as *mut _,
                 input_frame_count,
             )
         }
     }
 
     pub fn input_latency(&self) -> u64 {
-        unsafe { sys::ma_data_converter_get_input_latency(&self as *const _ as *mut _) }
+        unsafe { sys::ma_data_converter_get_input_latency(&self.0 as *const _ as *mut _) }
     }
 
     pub fn output_latency(&self) -> u64 {
-        unsafe { sys::ma_data_converter_get_output_latency(&self as *const _ as *mut _) }
+        unsafe { sys::ma_data_converter_get_output_latency(&self.0 as *const _ as *mut _) }
     }
 }
 
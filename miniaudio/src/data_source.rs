@@ -0,0 +1,506 @@
+use crate::audio_buffer::{AudioBuffer, AudioBufferRef, RawAudioBuffer};
+use crate::base::*;
+use crate::decoder::{Decoder, RawDecoder, SyncDecoder};
+use crate::frames::FramesMut;
+use crate::generation::{Noise, Waveform};
+use miniaudio_sys as sys;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+/// The format, channel count and sample rate of the frames produced by a data source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DataFormat {
+    pub format: Format,
+    pub channels: u32,
+    pub sample_rate: u32,
+}
+
+/// A source of PCM frames that can be read from and seeked.
+pub trait DataSource {
+    /// Reads frames into `output`, returning the number of frames that were read. Fewer frames
+    /// than requested will be read once the end of the source has been reached.
+    fn read_frames(&mut self, output: &mut FramesMut) -> Result<u64, Error>;
+
+    /// Seeks to the frame at `frame_index`.
+    fn seek_to_frame(&mut self, frame_index: u64) -> Result<(), Error>;
+
+    /// The index of the next frame that will be read. Sources that can't report their position
+    /// return `Error::NotImplemented`.
+    fn cursor_in_frames(&self) -> Result<u64, Error> {
+        Err(Error::NotImplemented)
+    }
+
+    /// The total number of frames in the source. Sources that have no fixed length (or can't
+    /// report it) return `Error::NotImplemented`.
+    fn length_in_frames(&self) -> Result<u64, Error> {
+        Err(Error::NotImplemented)
+    }
+
+    /// The format of the frames produced by this source.
+    fn data_format(&self) -> Result<DataFormat, Error>;
+}
+
+/// A type that is backed by a miniaudio `ma_data_source` and can be passed to miniaudio APIs that
+/// expect one.
+///
+/// # Safety
+///
+/// The returned pointer must point to a struct that begins with `ma_data_source_callbacks` and
+/// must remain valid for as long as `self` is borrowed.
+pub unsafe trait AsRawDataSource {
+    fn as_raw_data_source(&self) -> *mut sys::ma_data_source;
+}
+
+macro_rules! impl_data_source {
+    ($T:ty $(, $lt:lifetime)?) => {
+        impl$(<$lt>)? DataSource for $T {
+            #[inline]
+            fn read_frames(&mut self, output: &mut FramesMut) -> Result<u64, Error> {
+                unsafe { raw_read_frames(self.as_raw_data_source(), output) }
+            }
+
+            #[inline]
+            fn seek_to_frame(&mut self, frame_index: u64) -> Result<(), Error> {
+                Error::from_c_result(unsafe {
+                    sys::ma_data_source_seek_to_pcm_frame(self.as_raw_data_source(), frame_index)
+                })
+            }
+
+            #[inline]
+            fn cursor_in_frames(&self) -> Result<u64, Error> {
+                let mut cursor = 0;
+                let result = unsafe {
+                    sys::ma_data_source_get_cursor_in_pcm_frames(
+                        self.as_raw_data_source(),
+                        &mut cursor,
+                    )
+                };
+                map_result!(result, cursor)
+            }
+
+            #[inline]
+            fn length_in_frames(&self) -> Result<u64, Error> {
+                let mut length = 0;
+                let result = unsafe {
+                    sys::ma_data_source_get_length_in_pcm_frames(
+                        self.as_raw_data_source(),
+                        &mut length,
+                    )
+                };
+                map_result!(result, length)
+            }
+
+            #[inline]
+            fn data_format(&self) -> Result<DataFormat, Error> {
+                unsafe { raw_data_format(self.as_raw_data_source()) }
+            }
+        }
+    };
+}
+
+unsafe fn raw_read_frames(
+    data_source: *mut sys::ma_data_source,
+    output: &mut FramesMut,
+) -> Result<u64, Error> {
+    let data_format = raw_data_format(data_source)?;
+
+    assert!(
+        output.format() == data_format.format,
+        "output and data source format did not match (output: {:?}, input: {:?})",
+        output.format(),
+        data_format.format
+    );
+
+    assert!(
+        output.channels() == data_format.channels,
+        "output and data source channel count did not match (output: {}, input: {})",
+        output.channels(),
+        data_format.channels
+    );
+
+    let mut frames_read = 0;
+    let result = sys::ma_data_source_read_pcm_frames(
+        data_source,
+        output.as_mut_ptr() as *mut _,
+        output.frame_count() as u64,
+        &mut frames_read,
+        to_bool32(false),
+    );
+
+    // Reaching the end of the source is reported as MA_AT_END, which we report as a short read.
+    if result == sys::MA_AT_END {
+        Ok(frames_read)
+    } else {
+        map_result!(result, frames_read)
+    }
+}
+
+unsafe fn raw_data_format(data_source: *mut sys::ma_data_source) -> Result<DataFormat, Error> {
+    let mut format = sys::ma_format_unknown;
+    let mut channels = 0;
+    let mut sample_rate = 0;
+
+    let result = sys::ma_data_source_get_data_format(
+        data_source,
+        &mut format,
+        &mut channels,
+        &mut sample_rate,
+    );
+
+    map_result!(
+        result,
+        DataFormat {
+            format: Format::from_c(format),
+            channels,
+            sample_rate,
+        }
+    )
+}
+
+unsafe impl AsRawDataSource for RawDecoder {
+    #[inline]
+    fn as_raw_data_source(&self) -> *mut sys::ma_data_source {
+        self as *const RawDecoder as *mut _
+    }
+}
+impl_data_source!(RawDecoder);
+
+unsafe impl AsRawDataSource for Decoder {
+    #[inline]
+    fn as_raw_data_source(&self) -> *mut sys::ma_data_source {
+        (**self).as_raw_data_source()
+    }
+}
+impl_data_source!(Decoder);
+
+// NOTE: `SyncDecoder` does not implement `AsRawDataSource` because that would allow the inner
+// decoder to be used without acquiring its lock.
+impl DataSource for SyncDecoder {
+    #[inline]
+    fn read_frames(&mut self, output: &mut FramesMut) -> Result<u64, Error> {
+        self.write().read_frames(output)
+    }
+
+    #[inline]
+    fn seek_to_frame(&mut self, frame_index: u64) -> Result<(), Error> {
+        self.write().seek_to_frame(frame_index)
+    }
+
+    #[inline]
+    fn cursor_in_frames(&self) -> Result<u64, Error> {
+        self.write().cursor_in_frames()
+    }
+
+    #[inline]
+    fn length_in_frames(&self) -> Result<u64, Error> {
+        self.write().length_in_frames()
+    }
+
+    #[inline]
+    fn data_format(&self) -> Result<DataFormat, Error> {
+        self.read().data_format()
+    }
+}
+
+unsafe impl AsRawDataSource for Waveform {
+    #[inline]
+    fn as_raw_data_source(&self) -> *mut sys::ma_data_source {
+        self as *const Waveform as *mut _
+    }
+}
+impl_data_source!(Waveform);
+
+unsafe impl AsRawDataSource for Noise {
+    #[inline]
+    fn as_raw_data_source(&self) -> *mut sys::ma_data_source {
+        self as *const Noise as *mut _
+    }
+}
+impl_data_source!(Noise);
+
+unsafe impl AsRawDataSource for RawAudioBuffer {
+    #[inline]
+    fn as_raw_data_source(&self) -> *mut sys::ma_data_source {
+        self as *const RawAudioBuffer as *mut _
+    }
+}
+impl_data_source!(RawAudioBuffer);
+
+unsafe impl AsRawDataSource for AudioBuffer {
+    #[inline]
+    fn as_raw_data_source(&self) -> *mut sys::ma_data_source {
+        (**self).as_raw_data_source()
+    }
+}
+impl_data_source!(AudioBuffer);
+
+unsafe impl<'a> AsRawDataSource for AudioBufferRef<'a> {
+    #[inline]
+    fn as_raw_data_source(&self) -> *mut sys::ma_data_source {
+        (**self).as_raw_data_source()
+    }
+}
+impl_data_source!(AudioBufferRef<'a>, 'a);
+
+/// Wraps a data source implemented in Rust so that it can be passed to miniaudio APIs that expect
+/// an `ma_data_source`.
+#[repr(C)]
+pub struct DataSourceAdapter<T: DataSource> {
+    // NOTE: This must be the first field because miniaudio reads the callbacks through the
+    // data source pointer.
+    callbacks: sys::ma_data_source_callbacks,
+    source: T,
+}
+
+impl<T: DataSource> DataSourceAdapter<T> {
+    pub fn new(source: T) -> DataSourceAdapter<T> {
+        let callbacks = sys::ma_data_source_callbacks {
+            onRead: Some(data_source_adapter_read::<T>),
+            onSeek: Some(data_source_adapter_seek::<T>),
+            onMap: None,
+            onUnmap: None,
+            onGetDataFormat: Some(data_source_adapter_get_data_format::<T>),
+            onGetCursor: Some(data_source_adapter_get_cursor::<T>),
+            onGetLength: Some(data_source_adapter_get_length::<T>),
+        };
+
+        DataSourceAdapter { callbacks, source }
+    }
+
+    #[inline]
+    pub fn get_ref(&self) -> &T {
+        &self.source
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.source
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.source
+    }
+}
+
+unsafe impl<T: DataSource> AsRawDataSource for DataSourceAdapter<T> {
+    #[inline]
+    fn as_raw_data_source(&self) -> *mut sys::ma_data_source {
+        self as *const DataSourceAdapter<T> as *mut _
+    }
+}
+
+impl<T: DataSource> DataSource for DataSourceAdapter<T> {
+    #[inline]
+    fn read_frames(&mut self, output: &mut FramesMut) -> Result<u64, Error> {
+        self.source.read_frames(output)
+    }
+
+    #[inline]
+    fn seek_to_frame(&mut self, frame_index: u64) -> Result<(), Error> {
+        self.source.seek_to_frame(frame_index)
+    }
+
+    #[inline]
+    fn cursor_in_frames(&self) -> Result<u64, Error> {
+        self.source.cursor_in_frames()
+    }
+
+    #[inline]
+    fn length_in_frames(&self) -> Result<u64, Error> {
+        self.source.length_in_frames()
+    }
+
+    #[inline]
+    fn data_format(&self) -> Result<DataFormat, Error> {
+        self.source.data_format()
+    }
+}
+
+#[inline]
+fn to_c_result(result: Result<(), Error>) -> sys::ma_result {
+    match result {
+        Ok(()) => sys::MA_SUCCESS as _,
+        Err(err) => err as _,
+    }
+}
+
+/// The size in bytes of the buffer on the stack that skipped frames are read into.
+const SKIP_CHUNK_SIZE: usize = 4096;
+
+/// Reads and discards up to `frame_count` frames from `source` in chunks, returning the number of
+/// frames that were skipped. This fails with `InvalidArgs` if a single frame does not fit in a
+/// chunk.
+fn skip_frames<T: DataSource>(
+    source: &mut T,
+    frame_count: u64,
+    data_format: DataFormat,
+) -> Result<u64, Error> {
+    // NOTE: This is stored as `u32` so that the chunk is aligned for every format.
+    let mut chunk = [0u32; SKIP_CHUNK_SIZE / std::mem::size_of::<u32>()];
+    let chunk =
+        unsafe { std::slice::from_raw_parts_mut(chunk.as_mut_ptr() as *mut u8, SKIP_CHUNK_SIZE) };
+
+    let bytes_per_frame = data_format.format.size_in_bytes() * data_format.channels as usize;
+    if bytes_per_frame == 0 || bytes_per_frame > SKIP_CHUNK_SIZE {
+        ma_debug_panic!(
+            "cannot skip frames of {} bytes (max: {})",
+            bytes_per_frame,
+            SKIP_CHUNK_SIZE
+        );
+        return Err(Error::InvalidArgs);
+    }
+    let chunk_frames = (SKIP_CHUNK_SIZE / bytes_per_frame) as u64;
+
+    let mut skipped = 0;
+    while skipped < frame_count {
+        let frames_to_read = chunk_frames.min(frame_count - skipped);
+        let mut output = FramesMut::wrap::<u8>(
+            &mut chunk[..frames_to_read as usize * bytes_per_frame],
+            data_format.format,
+            data_format.channels,
+        );
+
+        let frames_read = source.read_frames(&mut output)?;
+        skipped += frames_read;
+        if frames_read < frames_to_read {
+            break;
+        }
+    }
+
+    Ok(skipped)
+}
+
+// NOTE: Unwinding into C is undefined behavior so all of these report a panic as a generic error.
+
+unsafe extern "C" fn data_source_adapter_read<T: DataSource>(
+    data_source: *mut sys::ma_data_source,
+    frames_out: *mut std::ffi::c_void,
+    frame_count: u64,
+    frames_read_out: *mut u64,
+) -> sys::ma_result {
+    if !frames_read_out.is_null() {
+        *frames_read_out = 0;
+    }
+
+    if data_source.is_null() {
+        return sys::MA_INVALID_ARGS;
+    }
+
+    let adapter = &mut *data_source.cast::<DataSourceAdapter<T>>();
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        let data_format = adapter.source.data_format()?;
+        let bytes_per_frame = data_format.format.size_in_bytes() * data_format.channels as usize;
+
+        // miniaudio passes a NULL output to skip frames without writing them (this is how
+        // `ma_data_source_seek_pcm_frames` is implemented).
+        if frames_out.is_null() {
+            return skip_frames(&mut adapter.source, frame_count, data_format);
+        }
+
+        let mut output = FramesMut::wrap::<u8>(
+            std::slice::from_raw_parts_mut(
+                frames_out.cast(),
+                frame_count as usize * bytes_per_frame,
+            ),
+            data_format.format,
+            data_format.channels,
+        );
+        adapter.source.read_frames(&mut output)
+    }));
+
+    match result {
+        Ok(Ok(frames_read)) => {
+            if !frames_read_out.is_null() {
+                *frames_read_out = frames_read;
+            }
+
+            // miniaudio uses MA_AT_END to know when a looping read has to seek back to the start.
+            if frames_read < frame_count {
+                sys::MA_AT_END
+            } else {
+                sys::MA_SUCCESS as _
+            }
+        }
+        Ok(Err(err)) => err as _,
+        Err(_) => sys::MA_ERROR,
+    }
+}
+
+unsafe extern "C" fn data_source_adapter_seek<T: DataSource>(
+    data_source: *mut sys::ma_data_source,
+    frame_index: u64,
+) -> sys::ma_result {
+    if data_source.is_null() {
+        return sys::MA_INVALID_ARGS;
+    }
+
+    let adapter = &mut *data_source.cast::<DataSourceAdapter<T>>();
+    catch_unwind(AssertUnwindSafe(|| {
+        to_c_result(adapter.source.seek_to_frame(frame_index))
+    }))
+    .unwrap_or(sys::MA_ERROR)
+}
+
+unsafe extern "C" fn data_source_adapter_get_data_format<T: DataSource>(
+    data_source: *mut sys::ma_data_source,
+    format_out: *mut sys::ma_format,
+    channels_out: *mut u32,
+    sample_rate_out: *mut u32,
+) -> sys::ma_result {
+    if data_source.is_null() {
+        return sys::MA_INVALID_ARGS;
+    }
+
+    let adapter = &*data_source.cast::<DataSourceAdapter<T>>();
+    catch_unwind(AssertUnwindSafe(|| {
+        let data_format = adapter.source.data_format()?;
+        if !format_out.is_null() {
+            *format_out = data_format.format as _;
+        }
+        if !channels_out.is_null() {
+            *channels_out = data_format.channels;
+        }
+        if !sample_rate_out.is_null() {
+            *sample_rate_out = data_format.sample_rate;
+        }
+        Ok(())
+    }))
+    .map(to_c_result)
+    .unwrap_or(sys::MA_ERROR)
+}
+
+unsafe extern "C" fn data_source_adapter_get_cursor<T: DataSource>(
+    data_source: *mut sys::ma_data_source,
+    cursor_out: *mut u64,
+) -> sys::ma_result {
+    if data_source.is_null() || cursor_out.is_null() {
+        return sys::MA_INVALID_ARGS;
+    }
+
+    let adapter = &*data_source.cast::<DataSourceAdapter<T>>();
+    catch_unwind(AssertUnwindSafe(|| {
+        adapter.source.cursor_in_frames().map(|cursor| {
+            *cursor_out = cursor;
+        })
+    }))
+    .map(to_c_result)
+    .unwrap_or(sys::MA_ERROR)
+}
+
+unsafe extern "C" fn data_source_adapter_get_length<T: DataSource>(
+    data_source: *mut sys::ma_data_source,
+    length_out: *mut u64,
+) -> sys::ma_result {
+    if data_source.is_null() || length_out.is_null() {
+        return sys::MA_INVALID_ARGS;
+    }
+
+    let adapter = &*data_source.cast::<DataSourceAdapter<T>>();
+    catch_unwind(AssertUnwindSafe(|| {
+        adapter.source.length_in_frames().map(|length| {
+            *length_out = length;
+        })
+    }))
+    .map(to_c_result)
+    .unwrap_or(sys::MA_ERROR)
+}
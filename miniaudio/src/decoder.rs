@@ -1,6 +1,7 @@
 use crate::base::*;
 use crate::lock::{RwLockReadGuard, RwLockWriteGuard, SpinRwLock};
-use crate::{Error, Format, FramesMut};
+use crate::vfs::VfsHandle;
+use crate::{Error, Format, Frames, FramesMut, Sample};
 use miniaudio_sys as sys;
 use std::ffi::CString;
 use std::io;
@@ -22,6 +23,18 @@ impl DecoderConfig {
     }
 }
 
+/// The container/codec of the data that a decoder reads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EncodingFormat {
+    /// Detect the encoding format from the data.
+    #[default]
+    Auto,
+    Wav,
+    Flac,
+    Mp3,
+    Vorbis,
+}
+
 #[repr(transparent)]
 pub struct RawDecoder {
     inner: sys::ma_decoder,
@@ -46,11 +59,48 @@ impl RawDecoder {
         }
     }
 
+    /// Reads frames into `output` like `read_pcm_frames` but also reports whether the end of the
+    /// stream was reached. The decoder only returns fewer frames than requested once it has run
+    /// out of frames, so a short read is reported as the end of the stream.
+    #[inline]
+    pub fn read_pcm_frames_with_status(&mut self, output: &mut FramesMut) -> DecoderReadResult {
+        let frames_read = self.read_pcm_frames(output);
+        DecoderReadResult {
+            frames_read,
+            end_of_stream: frames_read < output.frame_count() as u64,
+        }
+    }
+
     #[inline]
     pub fn length_in_pcm_frames(&mut self) -> u64 {
         unsafe { sys::ma_decoder_get_length_in_pcm_frames(&self.inner as *const _ as *mut _) }
     }
 
+    /// The index of the next frame that will be read from the decoder.
+    #[inline]
+    pub fn cursor_in_pcm_frames(&self) -> u64 {
+        let mut cursor = 0;
+        let result = unsafe {
+            sys::ma_decoder_get_cursor_in_pcm_frames(&self.inner as *const _ as *mut _, &mut cursor)
+        };
+
+        // This shouldn't fail because our arguments are valid, but we debug assert just to be sure.
+        debug_assert!(result == 0);
+
+        cursor
+    }
+
+    /// The number of frames between the cursor and the end of the stream. This will fail with
+    /// `NotImplemented` if the length of the stream can't be determined by the decoder.
+    #[inline]
+    pub fn available_frames(&mut self) -> Result<u64, Error> {
+        let mut available = 0;
+        let result = unsafe {
+            sys::ma_decoder_get_available_frames(&self.inner as *const _ as *mut _, &mut available)
+        };
+        map_result!(result, available)
+    }
+
     #[inline]
     pub fn seek_to_pcm_frame(&mut self, frame_index: u64) -> Result<(), Error> {
         Error::from_c_result(unsafe {
@@ -71,6 +121,16 @@ impl RawDecoder {
     }
 }
 
+/// The result of reading frames from a decoder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecoderReadResult {
+    /// The number of frames that were read.
+    pub frames_read: u64,
+
+    /// True if the decoder reached the end of the stream during this read.
+    pub end_of_stream: bool,
+}
+
 impl Drop for RawDecoder {
     fn drop(&mut self) {
         Error::from_c_result(unsafe { sys::ma_decoder_uninit(&mut self.inner) })
@@ -84,6 +144,10 @@ impl Drop for RawDecoder {
 pub struct SyncDecoder {
     inner: Arc<SpinRwLock<RawDecoder>>,
     has_reader: bool,
+
+    // NOTE: This must be declared after `inner` so that it is dropped after the decoder has been
+    // uninitialized. Uninitializing a decoder will close its file through the VFS.
+    vfs: Option<VfsHandle>,
 }
 
 impl SyncDecoder {
@@ -93,18 +157,24 @@ impl SyncDecoder {
         file: P,
         config: Option<&DecoderConfig>,
     ) -> Result<Self, Error> {
-        let decoder = Arc::new(SpinRwLock::new(MaybeUninit::<RawDecoder>::uninit()));
+        Self::from_file_with_format(file, EncodingFormat::Auto, config)
+    }
 
-        let filename = file
-            .as_ref()
-            .to_str()
-            .ok_or(Error::InvalidFile)
-            .and_then(|s| CString::new(s.to_string()).map_err(|_err| Error::InvalidFile))?;
+    /// Creates a `SyncDecoder` from a file that is decoded using the given encoding format instead
+    /// of detecting it. This will fail with an `InvalidFile` error if the path is not valid utf-8.
+    pub fn from_file_with_format<P: AsRef<Path>>(
+        file: P,
+        encoding_format: EncodingFormat,
+        config: Option<&DecoderConfig>,
+    ) -> Result<Self, Error> {
+        let decoder = Arc::new(SpinRwLock::new(MaybeUninit::<RawDecoder>::uninit()));
+        let filename = path_to_cstring(file.as_ref())?;
 
         let result = unsafe {
-            sys::ma_decoder_init_file(
-                filename.as_ptr() as *const _,
-                config.map(|c| &c.0 as *const _).unwrap_or(std::ptr::null()),
+            decoder_init_file(
+                &filename,
+                encoding_format,
+                config,
                 Arc::deref(&decoder).as_ptr() as *mut _,
             )
         };
@@ -114,6 +184,7 @@ impl SyncDecoder {
             SyncDecoder {
                 inner: unsafe { std::mem::transmute(decoder) },
                 has_reader: false,
+                vfs: None,
             }
         )
     }
@@ -125,6 +196,14 @@ impl SyncDecoder {
         Self::from_read(std::io::Cursor::new(data.into()), config)
     }
 
+    pub fn from_memory_with_format<M: Into<Vec<u8>>>(
+        data: M,
+        encoding_format: EncodingFormat,
+        config: Option<&DecoderConfig>,
+    ) -> Result<Self, Error> {
+        Self::from_read_with_format(std::io::Cursor::new(data.into()), encoding_format, config)
+    }
+
     pub fn from_read<T: 'static + SeekRead>(
         reader: T,
         config: Option<&DecoderConfig>,
@@ -132,20 +211,135 @@ impl SyncDecoder {
         Self::from_boxed_read(Box::new(reader), config)
     }
 
+    pub fn from_read_with_format<T: 'static + SeekRead>(
+        reader: T,
+        encoding_format: EncodingFormat,
+        config: Option<&DecoderConfig>,
+    ) -> Result<Self, Error> {
+        Self::from_boxed_read_with_format(Box::new(reader), encoding_format, config)
+    }
+
     pub fn from_boxed_read(
         reader: Box<dyn SeekRead>,
         config: Option<&DecoderConfig>,
+    ) -> Result<Self, Error> {
+        Self::from_boxed_read_with_format(reader, EncodingFormat::Auto, config)
+    }
+
+    pub fn from_boxed_read_with_format(
+        reader: Box<dyn SeekRead>,
+        encoding_format: EncodingFormat,
+        config: Option<&DecoderConfig>,
     ) -> Result<Self, Error> {
         let decoder = Arc::new(SpinRwLock::new(MaybeUninit::<RawDecoder>::uninit()));
 
-        let user_data = Box::new(reader);
+        let result = unsafe {
+            decoder_init_reader(
+                reader,
+                DecoderInit::Encoded(encoding_format, config),
+                Arc::deref(&decoder).as_ptr() as *mut _,
+            )
+        };
+
+        map_result!(
+            result,
+            SyncDecoder {
+                inner: unsafe {
+                    std::mem::transmute::<
+                        Arc<SpinRwLock<MaybeUninit<RawDecoder>>>,
+                        Arc<SpinRwLock<RawDecoder>>,
+                    >(decoder)
+                },
+                has_reader: true,
+                vfs: None,
+            }
+        )
+    }
+
+    /// Creates a `SyncDecoder` that opens a file through a virtual file system. This will fail with an
+    /// `InvalidFile` error if the path contains a nul byte.
+    pub fn from_vfs(
+        vfs: &VfsHandle,
+        path: &str,
+        config: Option<&DecoderConfig>,
+    ) -> Result<Self, Error> {
+        Self::from_vfs_with_format(vfs, path, EncodingFormat::Auto, config)
+    }
+
+    /// Creates a `SyncDecoder` that opens a file through a virtual file system and decodes it using the
+    /// given encoding format instead of detecting it. This will fail with an `InvalidFile` error if
+    /// the path contains a nul byte.
+    pub fn from_vfs_with_format(
+        vfs: &VfsHandle,
+        path: &str,
+        encoding_format: EncodingFormat,
+        config: Option<&DecoderConfig>,
+    ) -> Result<Self, Error> {
+        let decoder = Arc::new(SpinRwLock::new(MaybeUninit::<RawDecoder>::uninit()));
+        let path = CString::new(path).map_err(|_err| Error::InvalidFile)?;
 
         let result = unsafe {
-            sys::ma_decoder_init(
-                Some(decoder_read_with_reader),
-                Some(decoder_seek_with_reader),
-                Box::into_raw(user_data) as *mut _,
-                config.map(|c| &c.0 as *const _).unwrap_or(std::ptr::null()),
+            decoder_init_vfs(
+                vfs,
+                &path,
+                encoding_format,
+                config,
+                Arc::deref(&decoder).as_ptr() as *mut _,
+            )
+        };
+
+        map_result!(
+            result,
+            SyncDecoder {
+                inner: unsafe {
+                    std::mem::transmute::<
+                        Arc<SpinRwLock<MaybeUninit<RawDecoder>>>,
+                        Arc<SpinRwLock<RawDecoder>>,
+                    >(decoder)
+                },
+                has_reader: false,
+                vfs: Some(vfs.clone()),
+            }
+        )
+    }
+
+    /// Creates a `SyncDecoder` that reads headerless PCM frames from memory. The format, channel
+    /// count and sample rate of the data are taken from `input_config` and the frames are
+    /// converted to the output format described by `output_config`.
+    pub fn from_memory_raw<M: Into<Vec<u8>>>(
+        data: M,
+        input_config: &DecoderConfig,
+        output_config: Option<&DecoderConfig>,
+    ) -> Result<Self, Error> {
+        Self::from_read_raw(
+            std::io::Cursor::new(data.into()),
+            input_config,
+            output_config,
+        )
+    }
+
+    /// Creates a `SyncDecoder` that reads headerless PCM frames from a reader. The format, channel
+    /// count and sample rate of the data are taken from `input_config` and the frames are
+    /// converted to the output format described by `output_config`.
+    pub fn from_read_raw<T: 'static + SeekRead>(
+        reader: T,
+        input_config: &DecoderConfig,
+        output_config: Option<&DecoderConfig>,
+    ) -> Result<Self, Error> {
+        Self::from_boxed_read_raw(Box::new(reader), input_config, output_config)
+    }
+
+    pub fn from_boxed_read_raw(
+        reader: Box<dyn SeekRead>,
+        input_config: &DecoderConfig,
+        output_config: Option<&DecoderConfig>,
+    ) -> Result<Self, Error> {
+        let decoder = Arc::new(SpinRwLock::new(MaybeUninit::<RawDecoder>::uninit()));
+
+        let result = unsafe {
+            decoder_init_reader(
+                reader,
+                DecoderInit::Raw(input_config, output_config),
                 Arc::deref(&decoder).as_ptr() as *mut _,
             )
         };
@@ -155,6 +349,7 @@ impl SyncDecoder {
             SyncDecoder {
                 inner: unsafe { std::mem::transmute(decoder) },
                 has_reader: true,
+                vfs: None,
             }
         )
     }
@@ -177,6 +372,13 @@ impl SyncDecoder {
         }
     }
 
+    /// This will block until the lock for the inner decoder is acquired before calling
+    /// `read_pcm_frames_with_status`.
+    #[inline]
+    pub fn read_pcm_frames_with_status(&self, output: &mut FramesMut) -> DecoderReadResult {
+        self.inner.write().read_pcm_frames_with_status(output)
+    }
+
     #[inline]
     pub fn seek_to_pcm_frame(&self, frame_index: u64) -> Result<(), Error> {
         self.inner.write().seek_to_pcm_frame(frame_index)
@@ -187,6 +389,16 @@ impl SyncDecoder {
         self.inner.write().length_in_pcm_frames()
     }
 
+    #[inline]
+    pub fn cursor_in_pcm_frames(&self) -> u64 {
+        self.inner.read().cursor_in_pcm_frames()
+    }
+
+    #[inline]
+    pub fn available_frames(&self) -> Result<u64, Error> {
+        self.inner.write().available_frames()
+    }
+
     #[inline]
     pub fn output_format(&self) -> Format {
         self.inner.read().output_format()
@@ -228,6 +440,7 @@ impl Clone for SyncDecoder {
         SyncDecoder {
             inner: Arc::clone(&self.inner),
             has_reader: self.has_reader,
+            vfs: self.vfs.clone(),
         }
     }
 }
@@ -252,6 +465,10 @@ unsafe impl Sync for SyncDecoder {}
 pub struct Decoder {
     inner: Box<RawDecoder>,
     has_reader: bool,
+
+    // NOTE: This must be declared after `inner` so that it is dropped after the decoder has been
+    // uninitialized. Uninitializing a decoder will close its file through the VFS.
+    _vfs: Option<VfsHandle>,
 }
 
 impl Decoder {
@@ -260,18 +477,25 @@ impl Decoder {
     pub fn from_file<P: AsRef<Path>>(
         file: P,
         config: Option<&DecoderConfig>,
+    ) -> Result<Self, Error> {
+        Self::from_file_with_format(file, EncodingFormat::Auto, config)
+    }
+
+    /// Creates a `Decoder` from a file that is decoded using the given encoding format instead of
+    /// detecting it. This will fail with an `InvalidFile` error if the path is not valid utf-8.
+    pub fn from_file_with_format<P: AsRef<Path>>(
+        file: P,
+        encoding_format: EncodingFormat,
+        config: Option<&DecoderConfig>,
     ) -> Result<Self, Error> {
         let decoder = Box::new(MaybeUninit::<RawDecoder>::uninit());
-        let filename = file
-            .as_ref()
-            .to_str()
-            .ok_or(Error::InvalidFile)
-            .and_then(|s| CString::new(s.to_string()).map_err(|_err| Error::InvalidFile))?;
+        let filename = path_to_cstring(file.as_ref())?;
 
         let result = unsafe {
-            sys::ma_decoder_init_file(
-                filename.as_ptr() as *const _,
-                config.map(|c| &c.0 as *const _).unwrap_or(std::ptr::null()),
+            decoder_init_file(
+                &filename,
+                encoding_format,
+                config,
                 decoder.as_ptr() as *mut _,
             )
         };
@@ -281,6 +505,7 @@ impl Decoder {
             Decoder {
                 inner: unsafe { std::mem::transmute(decoder) },
                 has_reader: false,
+                _vfs: None,
             }
         )
     }
@@ -292,6 +517,14 @@ impl Decoder {
         Self::from_read(std::io::Cursor::new(data.into()), config)
     }
 
+    pub fn from_memory_with_format<M: Into<Vec<u8>>>(
+        data: M,
+        encoding_format: EncodingFormat,
+        config: Option<&DecoderConfig>,
+    ) -> Result<Self, Error> {
+        Self::from_read_with_format(std::io::Cursor::new(data.into()), encoding_format, config)
+    }
+
     pub fn from_read<T: 'static + SeekRead>(
         reader: T,
         config: Option<&DecoderConfig>,
@@ -299,19 +532,32 @@ impl Decoder {
         Self::from_boxed_read(Box::new(reader), config)
     }
 
+    pub fn from_read_with_format<T: 'static + SeekRead>(
+        reader: T,
+        encoding_format: EncodingFormat,
+        config: Option<&DecoderConfig>,
+    ) -> Result<Self, Error> {
+        Self::from_boxed_read_with_format(Box::new(reader), encoding_format, config)
+    }
+
     pub fn from_boxed_read(
         reader: Box<dyn SeekRead>,
         config: Option<&DecoderConfig>,
+    ) -> Result<Self, Error> {
+        Self::from_boxed_read_with_format(reader, EncodingFormat::Auto, config)
+    }
+
+    pub fn from_boxed_read_with_format(
+        reader: Box<dyn SeekRead>,
+        encoding_format: EncodingFormat,
+        config: Option<&DecoderConfig>,
     ) -> Result<Self, Error> {
         let decoder = Box::new(MaybeUninit::<RawDecoder>::uninit());
-        let user_data = Box::new(reader);
 
         let result = unsafe {
-            sys::ma_decoder_init(
-                Some(decoder_read_with_reader),
-                Some(decoder_seek_with_reader),
-                Box::into_raw(user_data) as *mut _,
-                config.map(|c| &c.0 as *const _).unwrap_or(std::ptr::null()),
+            decoder_init_reader(
+                reader,
+                DecoderInit::Encoded(encoding_format, config),
                 decoder.as_ptr() as *mut _,
             )
         };
@@ -321,9 +567,210 @@ impl Decoder {
             Decoder {
                 inner: unsafe { std::mem::transmute(decoder) },
                 has_reader: true,
+                _vfs: None,
             }
         )
     }
+
+    /// Creates a `Decoder` that opens a file through a virtual file system. This will fail with an
+    /// `InvalidFile` error if the path contains a nul byte.
+    pub fn from_vfs(
+        vfs: &VfsHandle,
+        path: &str,
+        config: Option<&DecoderConfig>,
+    ) -> Result<Self, Error> {
+        Self::from_vfs_with_format(vfs, path, EncodingFormat::Auto, config)
+    }
+
+    /// Creates a `Decoder` that opens a file through a virtual file system and decodes it using the
+    /// given encoding format instead of detecting it. This will fail with an `InvalidFile` error if
+    /// the path contains a nul byte.
+    pub fn from_vfs_with_format(
+        vfs: &VfsHandle,
+        path: &str,
+        encoding_format: EncodingFormat,
+        config: Option<&DecoderConfig>,
+    ) -> Result<Self, Error> {
+        let decoder = Box::new(MaybeUninit::<RawDecoder>::uninit());
+        let path = CString::new(path).map_err(|_err| Error::InvalidFile)?;
+
+        let result = unsafe {
+            decoder_init_vfs(
+                vfs,
+                &path,
+                encoding_format,
+                config,
+                decoder.as_ptr() as *mut _,
+            )
+        };
+
+        map_result!(
+            result,
+            Decoder {
+                inner: unsafe {
+                    std::mem::transmute::<Box<MaybeUninit<RawDecoder>>, Box<RawDecoder>>(decoder)
+                },
+                has_reader: false,
+                _vfs: Some(vfs.clone()),
+            }
+        )
+    }
+
+    /// Creates a `Decoder` that reads headerless PCM frames from memory. The format, channel count
+    /// and sample rate of the data are taken from `input_config` and the frames are converted to
+    /// the output format described by `output_config`.
+    pub fn from_memory_raw<M: Into<Vec<u8>>>(
+        data: M,
+        input_config: &DecoderConfig,
+        output_config: Option<&DecoderConfig>,
+    ) -> Result<Self, Error> {
+        Self::from_read_raw(
+            std::io::Cursor::new(data.into()),
+            input_config,
+            output_config,
+        )
+    }
+
+    /// Creates a `Decoder` that reads headerless PCM frames from a reader. The format, channel
+    /// count and sample rate of the data are taken from `input_config` and the frames are
+    /// converted to the output format described by `output_config`.
+    pub fn from_read_raw<T: 'static + SeekRead>(
+        reader: T,
+        input_config: &DecoderConfig,
+        output_config: Option<&DecoderConfig>,
+    ) -> Result<Self, Error> {
+        Self::from_boxed_read_raw(Box::new(reader), input_config, output_config)
+    }
+
+    pub fn from_boxed_read_raw(
+        reader: Box<dyn SeekRead>,
+        input_config: &DecoderConfig,
+        output_config: Option<&DecoderConfig>,
+    ) -> Result<Self, Error> {
+        let decoder = Box::new(MaybeUninit::<RawDecoder>::uninit());
+
+        let result = unsafe {
+            decoder_init_reader(
+                reader,
+                DecoderInit::Raw(input_config, output_config),
+                decoder.as_ptr() as *mut _,
+            )
+        };
+
+        map_result!(
+            result,
+            Decoder {
+                inner: unsafe {
+                    std::mem::transmute::<Box<MaybeUninit<RawDecoder>>, Box<RawDecoder>>(decoder)
+                },
+                has_reader: true,
+                _vfs: None,
+            }
+        )
+    }
+}
+
+/// How the data read by a decoder is encoded.
+enum DecoderInit<'c> {
+    Encoded(EncodingFormat, Option<&'c DecoderConfig>),
+    Raw(&'c DecoderConfig, Option<&'c DecoderConfig>),
+}
+
+fn path_to_cstring(path: &Path) -> Result<CString, Error> {
+    path.to_str()
+        .ok_or(Error::InvalidFile)
+        .and_then(|s| CString::new(s.to_string()).map_err(|_err| Error::InvalidFile))
+}
+
+#[inline]
+fn config_ptr(config: Option<&DecoderConfig>) -> *const sys::ma_decoder_config {
+    config.map(|c| &c.0 as *const _).unwrap_or(std::ptr::null())
+}
+
+unsafe fn decoder_init_file(
+    filename: &CString,
+    encoding_format: EncodingFormat,
+    config: Option<&DecoderConfig>,
+    decoder: *mut sys::ma_decoder,
+) -> sys::ma_result {
+    let init = match encoding_format {
+        EncodingFormat::Auto => sys::ma_decoder_init_file,
+        EncodingFormat::Wav => sys::ma_decoder_init_file_wav,
+        EncodingFormat::Flac => sys::ma_decoder_init_file_flac,
+        EncodingFormat::Mp3 => sys::ma_decoder_init_file_mp3,
+        EncodingFormat::Vorbis => sys::ma_decoder_init_file_vorbis,
+    };
+
+    init(filename.as_ptr() as *const _, config_ptr(config), decoder)
+}
+
+unsafe fn decoder_init_vfs(
+    vfs: &VfsHandle,
+    path: &CString,
+    encoding_format: EncodingFormat,
+    config: Option<&DecoderConfig>,
+    decoder: *mut sys::ma_decoder,
+) -> sys::ma_result {
+    let init = match encoding_format {
+        EncodingFormat::Auto => sys::ma_decoder_init_vfs,
+        EncodingFormat::Wav => sys::ma_decoder_init_vfs_wav,
+        EncodingFormat::Flac => sys::ma_decoder_init_vfs_flac,
+        EncodingFormat::Mp3 => sys::ma_decoder_init_vfs_mp3,
+        EncodingFormat::Vorbis => sys::ma_decoder_init_vfs_vorbis,
+    };
+
+    init(
+        vfs.as_raw_vfs(),
+        path.as_ptr() as *const _,
+        config_ptr(config),
+        decoder,
+    )
+}
+
+/// Initializes a decoder that reads from `reader`. Ownership of the reader is passed to the
+/// decoder as its user data if this succeeds, otherwise the reader is dropped.
+unsafe fn decoder_init_reader(
+    reader: Box<dyn SeekRead>,
+    init: DecoderInit,
+    decoder: *mut sys::ma_decoder,
+) -> sys::ma_result {
+    let user_data = Box::into_raw(Box::new(reader));
+
+    let result = match init {
+        DecoderInit::Encoded(encoding_format, config) => {
+            let init = match encoding_format {
+                EncodingFormat::Auto => sys::ma_decoder_init,
+                EncodingFormat::Wav => sys::ma_decoder_init_wav,
+                EncodingFormat::Flac => sys::ma_decoder_init_flac,
+                EncodingFormat::Mp3 => sys::ma_decoder_init_mp3,
+                EncodingFormat::Vorbis => sys::ma_decoder_init_vorbis,
+            };
+
+            init(
+                Some(decoder_read_with_reader),
+                Some(decoder_seek_with_reader),
+                user_data as *mut _,
+                config_ptr(config),
+                decoder,
+            )
+        }
+
+        DecoderInit::Raw(input_config, output_config) => sys::ma_decoder_init_raw(
+            Some(decoder_read_with_reader),
+            Some(decoder_seek_with_reader),
+            user_data as *mut _,
+            &input_config.0,
+            config_ptr(output_config),
+            decoder,
+        ),
+    };
+
+    if Error::is_c_error(result) {
+        // Recreate the box and allow it to be dropped.
+        let _reader: Box<Box<dyn SeekRead>> = Box::from_raw(user_data);
+    }
+
+    result
 }
 
 pub trait SeekRead: io::Read + io::Seek {}
@@ -398,3 +845,181 @@ impl Drop for Decoder {
 
 unsafe impl Send for Decoder {}
 unsafe impl Sync for Decoder {}
+
+/// PCM frames that were fully decoded in one go by `decode_file` or `decode_memory`.
+pub struct DecodedAudio {
+    data: *mut std::ffi::c_void,
+    frame_count: u64,
+    format: Format,
+    channels: u32,
+    sample_rate: u32,
+}
+
+impl DecodedAudio {
+    /// Takes ownership of frames allocated by miniaudio and reads the output format back out of
+    /// the config that was used to decode them.
+    fn from_decoded(
+        data: *mut std::ffi::c_void,
+        frame_count: u64,
+        config: &sys::ma_decoder_config,
+    ) -> DecodedAudio {
+        DecodedAudio {
+            data,
+            frame_count,
+            format: Format::from_c(config.format),
+            channels: config.channels,
+            sample_rate: config.sampleRate,
+        }
+    }
+
+    /// Borrows the decoded frames.
+    #[inline]
+    pub fn frames(&self) -> Frames<'_> {
+        Frames::wrap::<u8>(self.as_bytes(), self.format, self.channels)
+    }
+
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        if self.data.is_null() {
+            return &[];
+        }
+
+        let byte_count =
+            self.frame_count as usize * self.format.size_in_bytes() * self.channels as usize;
+        unsafe { std::slice::from_raw_parts(self.data.cast::<u8>(), byte_count) }
+    }
+
+    /// Borrows the decoded frames as interleaved samples. This will panic if `S` does not match
+    /// the format of the decoded frames.
+    #[inline]
+    pub fn as_samples<S: Sample>(&self) -> &[S] {
+        assert!(
+            self.format == S::format(),
+            "format mismatch (frames: {:?}, requested: {:?})",
+            self.format,
+            S::format()
+        );
+
+        if self.data.is_null() {
+            return &[];
+        }
+
+        let sample_count = self.frame_count as usize * self.channels as usize;
+        unsafe { std::slice::from_raw_parts(self.data.cast::<S>(), sample_count) }
+    }
+
+    /// Copies the decoded frames into a vector of interleaved samples, freeing the memory that was
+    /// allocated by miniaudio. This will panic if `S` does not match the format of the decoded
+    /// frames.
+    pub fn into_samples<S: Sample + Clone>(self) -> Vec<S> {
+        self.as_samples::<S>().to_vec()
+    }
+
+    #[inline]
+    pub fn frame_count(&self) -> u64 {
+        self.frame_count
+    }
+
+    #[inline]
+    pub fn format(&self) -> Format {
+        self.format
+    }
+
+    #[inline]
+    pub fn channels(&self) -> u32 {
+        self.channels
+    }
+
+    #[inline]
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+}
+
+impl Drop for DecodedAudio {
+    fn drop(&mut self) {
+        // NOTE: The frames are allocated using the default allocation callbacks because we never
+        // set any on the decoder config.
+        if !self.data.is_null() {
+            unsafe { sys::ma_free(self.data, std::ptr::null()) };
+        }
+    }
+}
+
+unsafe impl Send for DecodedAudio {}
+unsafe impl Sync for DecodedAudio {}
+
+/// Decodes an entire file into memory. The frames are converted to the output format described by
+/// `config`. This will fail with an `InvalidFile` error if the path is not valid utf-8.
+pub fn decode_file<P: AsRef<Path>>(file: P, config: &DecoderConfig) -> Result<DecodedAudio, Error> {
+    let filename = path_to_cstring(file.as_ref())?;
+    let mut config = config.0;
+    let mut frame_count = 0;
+    let mut data = std::ptr::null_mut();
+
+    let result = unsafe {
+        sys::ma_decode_file(
+            filename.as_ptr() as *const _,
+            &mut config,
+            &mut frame_count,
+            &mut data,
+        )
+    };
+
+    map_result!(
+        result,
+        DecodedAudio::from_decoded(data, frame_count, &config)
+    )
+}
+
+/// Decodes an entire encoded file that is already in memory. The frames are converted to the
+/// output format described by `config`.
+pub fn decode_memory(data: &[u8], config: &DecoderConfig) -> Result<DecodedAudio, Error> {
+    let mut config = config.0;
+    let mut frame_count = 0;
+    let mut frames = std::ptr::null_mut();
+
+    let result = unsafe {
+        sys::ma_decode_memory(
+            data.as_ptr() as *const _,
+            data.len(),
+            &mut config,
+            &mut frame_count,
+            &mut frames,
+        )
+    };
+
+    map_result!(
+        result,
+        DecodedAudio::from_decoded(frames, frame_count, &config)
+    )
+}
+
+/// Decodes an entire file that is opened through a virtual file system. The frames are converted
+/// to the output format described by `config`. This will fail with an `InvalidFile` error if the
+/// path contains a nul byte.
+pub fn decode_from_vfs(
+    vfs: &VfsHandle,
+    path: &str,
+    config: &DecoderConfig,
+) -> Result<DecodedAudio, Error> {
+    let path = CString::new(path).map_err(|_err| Error::InvalidFile)?;
+    let mut config = config.0;
+    let mut frame_count = 0;
+    let mut data = std::ptr::null_mut();
+
+    let result = unsafe {
+        sys::ma_decode_from_vfs(
+            vfs.as_raw_vfs(),
+            path.as_ptr() as *const _,
+            &mut config,
+            &mut frame_count,
+            &mut data,
+        )
+    };
+
+    map_result!(
+        result,
+        DecodedAudio::from_decoded(data, frame_count, &config)
+    )
+}
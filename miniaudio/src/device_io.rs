@@ -1,4 +1,5 @@
 use crate::base::*;
+use crate::channels::ChannelMap;
 use crate::frames::{Frames, FramesMut};
 use crate::resampling::ResampleAlgorithm;
 use miniaudio_sys as sys;
@@ -9,7 +10,7 @@ use std::os::raw::c_void;
 use std::panic::{catch_unwind, AssertUnwindSafe};
 use std::ptr;
 use std::ptr::NonNull;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 type MADeviceConfigPlayback = sys::ma_device_config__bindgen_ty_2;
 type MADeviceConfigCapture = sys::ma_device_config__bindgen_ty_3;
@@ -42,6 +43,114 @@ pub enum Backend {
 }
 impl_from_c!(Backend, sys::ma_backend);
 
+impl Backend {
+    /// Every backend in default priority order.
+    pub const ALL: [Backend; 14] = [
+        Backend::Wasapi,
+        Backend::DSound,
+        Backend::WinMM,
+        Backend::CoreAudio,
+        Backend::SNDIO,
+        Backend::Audio4,
+        Backend::OSS,
+        Backend::PulseAudio,
+        Backend::Alsa,
+        Backend::Jack,
+        Backend::AAudio,
+        Backend::OpenSL,
+        Backend::WebAudio,
+        Backend::Null,
+    ];
+
+    /// Returns the name that miniaudio uses for this backend, e.g. "PulseAudio" or "Core Audio".
+    pub fn name(self) -> &'static str {
+        let cstr = unsafe { CStr::from_ptr(sys::ma_get_backend_name(self as _)) };
+
+        // FIXME at the moment we just return a blank string instead of invalid UTF-8
+        cstr.to_str().unwrap_or("")
+    }
+
+    /// Returns true if support for this backend was compiled in. This is false for backends that
+    /// are not available on the target platform or that were disabled with one of the `ma-no-*`
+    /// features. An enabled backend may still fail to initialize at runtime.
+    pub fn is_enabled(self) -> bool {
+        from_bool32(unsafe { sys::ma_is_backend_enabled(self as _) })
+    }
+
+    /// Returns true if this backend supports `DeviceType::Loopback` devices. This does not require
+    /// a context, so it can be used to check for support before creating one.
+    pub fn supports_loopback(self) -> bool {
+        from_bool32(unsafe { sys::ma_is_loopback_supported(self as _) })
+    }
+}
+
+impl std::fmt::Display for Backend {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Parses a backend from either its name (see `Backend::name`) or the name of its variant. Case,
+/// spaces and punctuation are ignored, so "pulseaudio", "Core Audio" and "coreaudio" are all
+/// accepted.
+impl std::str::FromStr for Backend {
+    type Err = ParseBackendError;
+
+    fn from_str(s: &str) -> Result<Backend, ParseBackendError> {
+        fn normalize(s: &str) -> String {
+            s.chars()
+                .filter(char::is_ascii_alphanumeric)
+                .map(|c| c.to_ascii_lowercase())
+                .collect()
+        }
+
+        let wanted = normalize(s);
+        Backend::ALL
+            .iter()
+            .copied()
+            .find(|backend| {
+                normalize(backend.name()) == wanted
+                    || normalize(&format!("{:?}", backend)) == wanted
+            })
+            .ok_or_else(|| ParseBackendError(s.to_string()))
+    }
+}
+
+/// The error returned when a string does not name a backend.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseBackendError(String);
+
+impl std::fmt::Display for ParseBackendError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "unknown backend: {:?}", self.0)
+    }
+}
+
+impl std::error::Error for ParseBackendError {}
+
+/// Returns every backend that was compiled in, in default priority order. See
+/// `Backend::is_enabled`.
+pub fn enabled_backends() -> Vec<Backend> {
+    let mut backends = [0 as sys::ma_backend; 32];
+    let mut count = 0usize;
+    let result =
+        unsafe { sys::ma_get_enabled_backends(backends.as_mut_ptr(), backends.len(), &mut count) };
+    if Error::is_c_error(result) {
+        return Vec::new();
+    }
+
+    // NOTE: miniaudio also reports its custom backend, which doesn't have a variant here.
+    backends[..count]
+        .iter()
+        .filter_map(|&backend| {
+            Backend::ALL
+                .iter()
+                .copied()
+                .find(|&known| known as sys::ma_backend == backend)
+        })
+        .collect()
+}
+
 #[repr(C)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum ThreadPriority {
@@ -71,6 +180,10 @@ pub enum DeviceType {
     Playback = sys::ma_device_type_playback as _,
     Capture = sys::ma_device_type_capture as _,
     Duplex = sys::ma_device_type_duplex as _,
+
+    /// Captures the output of a playback device. Only some backends support this (currently only
+    /// WASAPI), which can be checked with `Backend::supports_loopback` or
+    /// `RawContext::is_loopback_supported`.
     Loopback = sys::ma_device_type_loopback as _,
 }
 impl_from_c!(DeviceType, sys::ma_device_type);
@@ -83,6 +196,68 @@ pub enum ShareMode {
 }
 impl_from_c!(ShareMode, sys::ma_share_mode);
 
+/// The state of a device as tracked by miniaudio.
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceState {
+    Uninitialized = sys::MA_STATE_UNINITIALIZED,
+    Stopped = sys::MA_STATE_STOPPED,
+    Started = sys::MA_STATE_STARTED,
+
+    /// The device is in the process of starting. It can't be started or stopped until it is
+    /// `Started`.
+    Starting = sys::MA_STATE_STARTING,
+
+    /// The device is in the process of stopping. It can't be started or stopped until it is
+    /// `Stopped`.
+    Stopping = sys::MA_STATE_STOPPING,
+}
+impl_from_c!(DeviceState, u32);
+
+/// An error returned when starting or stopping a device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceControlError {
+    /// The device was not in a state that allows the operation, e.g. trying to start a device that
+    /// is still `Stopping`. This contains the state that the device was in.
+    InvalidState(DeviceState),
+
+    /// The backend failed to start or stop the device.
+    Backend(Error),
+}
+
+impl std::fmt::Display for DeviceControlError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            DeviceControlError::InvalidState(state) => {
+                write!(f, "invalid device state ({:?})", state)
+            }
+            DeviceControlError::Backend(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for DeviceControlError {}
+
+impl From<Error> for DeviceControlError {
+    fn from(err: Error) -> DeviceControlError {
+        DeviceControlError::Backend(err)
+    }
+}
+
+impl From<DeviceControlError> for Error {
+    fn from(err: DeviceControlError) -> Error {
+        match err {
+            DeviceControlError::InvalidState(DeviceState::Uninitialized) => {
+                Error::DeviceNotInitialized
+            }
+            DeviceControlError::InvalidState(DeviceState::Started) => Error::DeviceNotStopped,
+            DeviceControlError::InvalidState(DeviceState::Stopped) => Error::DeviceNotStarted,
+            DeviceControlError::InvalidState(_) => Error::Busy,
+            DeviceControlError::Backend(err) => err,
+        }
+    }
+}
+
 #[repr(C)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum IOSSessionCategory {
@@ -129,6 +304,12 @@ impl DeviceIdAndName {
         self.0.name()
     }
 
+    /// Returns true if the backend reported this device as the default device while enumerating.
+    #[inline]
+    pub fn is_default(&self) -> bool {
+        self.0.is_default()
+    }
+
     /// Allows you to use this as the device info.
     /// # Safety
     /// Only ID and name are guaranteed to be initialzied. All other values may just be zero.
@@ -137,10 +318,79 @@ impl DeviceIdAndName {
     }
 }
 
+/// Identifies a device. The meaning of the ID depends on the backend that it came from.
+///
+/// Two IDs are equal if their bytes are equal, so IDs can be stored (see `as_bytes` and
+/// `from_bytes`) and compared with IDs from a later run using the same backend.
 #[repr(transparent)]
 #[derive(Clone)]
 pub struct DeviceId(sys::ma_device_id);
 
+impl DeviceId {
+    /// Returns the raw bytes of this ID.
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        unsafe {
+            std::slice::from_raw_parts(
+                &self.0 as *const sys::ma_device_id as *const u8,
+                std::mem::size_of::<sys::ma_device_id>(),
+            )
+        }
+    }
+
+    /// Creates an ID from bytes that were returned by `as_bytes`. Trailing zeroes may be left out.
+    /// This will return `None` if there are more bytes than fit in an ID.
+    pub fn from_bytes(bytes: &[u8]) -> Option<DeviceId> {
+        if bytes.len() > std::mem::size_of::<sys::ma_device_id>() {
+            return None;
+        }
+
+        let mut id = unsafe { std::mem::zeroed::<sys::ma_device_id>() };
+        unsafe {
+            std::ptr::copy_nonoverlapping(
+                bytes.as_ptr(),
+                &mut id as *mut sys::ma_device_id as *mut u8,
+                bytes.len(),
+            )
+        };
+        Some(DeviceId(id))
+    }
+
+    /// The bytes of this ID without any trailing zeroes.
+    fn significant_bytes(&self) -> &[u8] {
+        let bytes = self.as_bytes();
+        let len = bytes.iter().rposition(|&b| b != 0).map_or(0, |i| i + 1);
+        &bytes[..len]
+    }
+}
+
+impl PartialEq for DeviceId {
+    fn eq(&self, other: &DeviceId) -> bool {
+        self.as_bytes() == other.as_bytes()
+    }
+}
+
+impl Eq for DeviceId {}
+
+impl std::hash::Hash for DeviceId {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.as_bytes().hash(state);
+    }
+}
+
+impl std::fmt::Debug for DeviceId {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        // Most backends use strings for their IDs, which are a lot more readable than the bytes.
+        let bytes = self.significant_bytes();
+        match std::str::from_utf8(bytes) {
+            Ok(id) if id.chars().all(|c| !c.is_control()) => {
+                f.debug_tuple("DeviceId").field(&id).finish()
+            }
+            _ => f.debug_tuple("DeviceId").field(&bytes).finish(),
+        }
+    }
+}
+
 #[repr(transparent)]
 #[derive(Clone)]
 pub struct DeviceInfo(sys::ma_device_info);
@@ -159,6 +409,12 @@ impl DeviceInfo {
         cstr.to_str().unwrap_or("")
     }
 
+    /// Returns true if this is the default device. See `Context::device_info`.
+    #[inline]
+    pub fn is_default(&self) -> bool {
+        from_bool32(self.0.isDefault)
+    }
+
     #[inline]
     pub fn format_count(&self) -> u32 {
         self.0.formatCount
@@ -193,6 +449,61 @@ impl DeviceInfo {
     pub fn max_sample_rate(&self) -> u32 {
         self.0.maxSampleRate
     }
+
+    /// Returns the combinations of format, channels and sample rate that the device supports
+    /// without conversion.
+    #[inline]
+    pub fn native_data_formats(&self) -> &[NativeDataFormat] {
+        let count = (self.0.nativeDataFormatCount as usize).min(self.0.nativeDataFormats.len());
+        unsafe {
+            std::slice::from_raw_parts(
+                self.0.nativeDataFormats.as_ptr() as *const NativeDataFormat,
+                count,
+            )
+        }
+    }
+}
+
+/// A data format that a device supports natively.
+#[repr(transparent)]
+#[derive(Clone, Copy)]
+pub struct NativeDataFormat(sys::ma_device_info__bindgen_ty_1);
+
+impl NativeDataFormat {
+    /// Returns the sample format, or `Format::Unknown` if every format is supported.
+    #[inline]
+    pub fn format(&self) -> Format {
+        Format::from_c(self.0.format)
+    }
+
+    /// Returns the channel count, or 0 if any channel count is supported.
+    #[inline]
+    pub fn channels(&self) -> u32 {
+        self.0.channels
+    }
+
+    /// Returns the sample rate, or 0 if any sample rate is supported.
+    #[inline]
+    pub fn sample_rate(&self) -> u32 {
+        self.0.sampleRate
+    }
+
+    /// Returns true if this format is only supported in exclusive mode.
+    #[inline]
+    pub fn is_exclusive_mode(&self) -> bool {
+        self.0.flags & sys::MA_DATA_FORMAT_FLAG_EXCLUSIVE_MODE != 0
+    }
+}
+
+impl std::fmt::Debug for NativeDataFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("NativeDataFormat")
+            .field("format", &self.format())
+            .field("channels", &self.channels())
+            .field("sample_rate", &self.sample_rate())
+            .field("exclusive_mode", &self.is_exclusive_mode())
+            .finish()
+    }
 }
 
 #[repr(transparent)]
@@ -369,12 +680,31 @@ impl DeviceConfig {
         }
     }
 
+    /// Sets the state callback for this device config. See `Device::set_state_callback` for when
+    /// this is called.
+    ///
+    /// **IMPORTANT** The function passed in here must be cloneable because each device that uses
+    /// this config will create and use a clone of the given function and its environment. In order
+    /// to share a variable between distinct device instances they have to be wrapped in some sort
+    /// of cloneable thread-safe struct like an Arc.
+    pub fn set_state_callback<F>(&mut self, callback: F)
+    where
+        F: FnMut(&RawDevice, DeviceState) + Clone + Send + 'static,
+    {
+        let user_data = self.ensure_user_data();
+        unsafe {
+            (*user_data).state_callback_factory =
+                Some(Box::new(move || Box::new(callback.clone())));
+        }
+    }
+
     /// This will ensure that user data is initialized and return an unsafe mutable pointer to it.
     fn ensure_user_data(&mut self) -> *mut DeviceConfigUserData {
         if self.0.pUserData.is_null() {
             self.0.pUserData = Box::into_raw(Box::new(DeviceConfigUserData {
                 data_callback_factory: None,
                 stop_callback_factory: None,
+                state_callback_factory: None,
             })) as *mut _;
         }
         self.0.pUserData.cast()
@@ -392,12 +722,15 @@ enum MaybePoisoned<T> {
 
 pub type DataCallback = dyn FnMut(&RawDevice, &mut FramesMut, &Frames);
 pub type StopCallback = dyn FnMut(&RawDevice);
+pub type StateCallback = dyn FnMut(&RawDevice, DeviceState) + Send;
 pub type BoxedDataCallback = Box<DataCallback>;
 pub type BoxedStopCallback = Box<StopCallback>;
+pub type BoxedStateCallback = Box<StateCallback>;
 
 pub struct DeviceConfigUserData {
-    data_callback_factory: Option<Box<dyn Fn() -> BoxedDataCallback>>,
-    stop_callback_factory: Option<Box<dyn Fn() -> BoxedStopCallback>>,
+    data_callback_factory: Option<Box<dyn Fn() -> BoxedDataCallback + Send>>,
+    stop_callback_factory: Option<Box<dyn Fn() -> BoxedStopCallback + Send>>,
+    state_callback_factory: Option<Box<dyn Fn() -> BoxedStateCallback + Send>>,
 }
 
 // FIXME it might be better to just set the callbacks to some noop functions by default
@@ -405,6 +738,11 @@ pub struct DeviceConfigUserData {
 pub struct DeviceUserData {
     data_callback: MaybePoisoned<Option<BoxedDataCallback>>,
     stop_callback: MaybePoisoned<Option<BoxedStopCallback>>,
+
+    // NOTE: This is called from both the thread controlling the device and the audio thread, so
+    // it is behind a lock. A panic in the callback poisons the lock, after which the callback is
+    // never called again.
+    state_callback: Mutex<Option<BoxedStateCallback>>,
 }
 
 unsafe extern "C" fn device_data_callback_trampoline(
@@ -501,6 +839,17 @@ unsafe extern "C" fn device_stop_callback_trampoline(device_ptr: *mut sys::ma_de
                 (*user_data).data_callback = MaybePoisoned::Poison(data_callback_poison);
             };
         }
+
+        // NOTE: When the device is stopped with `ma_device_stop` it is already `Stopping` here. When
+        // the backend stops the device on its own it is still `Started` and only becomes `Stopped`
+        // after this returns, so there is no state that can be reported here without `state`
+        // contradicting it.
+        let _ = catch_unwind(AssertUnwindSafe(|| {
+            let device = device.as_ref();
+            if device.state() == DeviceState::Stopping {
+                device.notify_state(DeviceState::Stopping);
+            }
+        }));
     }
 }
 
@@ -520,6 +869,10 @@ impl Drop for DeviceConfig {
     }
 }
 
+// NOTE: The pointers in a device config are all owned by the config and the callbacks that can be
+// set on it are all required to be `Send`.
+unsafe impl Send for DeviceConfig {}
+
 #[repr(transparent)]
 pub struct DeviceConfigPlayback(MADeviceConfigPlayback);
 
@@ -559,11 +912,16 @@ impl DeviceConfigPlayback {
         self.0.channels = channels;
     }
 
-    pub fn channel_map(&self) -> &[Channel; MAX_CHANNELS] {
-        unsafe {
-            &*(&self.0.channelMap as *const [sys::ma_channel; MAX_CHANNELS]
-                as *const [Channel; MAX_CHANNELS])
-        }
+    /// Returns the channel map for the configured number of channels.
+    pub fn channel_map(&self) -> ChannelMap {
+        ChannelMap::from_raw(&self.0.channelMap, self.0.channels)
+    }
+
+    /// Sets the channel map. This also sets the number of channels to the number of channels in
+    /// the channel map.
+    pub fn set_channel_map(&mut self, channel_map: &ChannelMap) {
+        channel_map.copy_to_raw(&mut self.0.channelMap);
+        self.0.channels = channel_map.channels();
     }
 
     pub fn channel_map_mut(&mut self) -> &mut [Channel; MAX_CHANNELS] {
@@ -635,11 +993,16 @@ impl DeviceConfigCapture {
         self.0.channels = channels;
     }
 
-    pub fn channel_map(&self) -> &[Channel; MAX_CHANNELS] {
-        unsafe {
-            &*(&self.0.channelMap as *const [sys::ma_channel; MAX_CHANNELS]
-                as *const [Channel; MAX_CHANNELS])
-        }
+    /// Returns the channel map for the configured number of channels.
+    pub fn channel_map(&self) -> ChannelMap {
+        ChannelMap::from_raw(&self.0.channelMap, self.0.channels)
+    }
+
+    /// Sets the channel map. This also sets the number of channels to the number of channels in
+    /// the channel map.
+    pub fn set_channel_map(&mut self, channel_map: &ChannelMap) {
+        channel_map.copy_to_raw(&mut self.0.channelMap);
+        self.0.channels = channel_map.channels();
     }
 
     pub fn channel_map_mut(&mut self) -> &mut [Channel; MAX_CHANNELS] {
@@ -1023,6 +1386,11 @@ impl RawContext {
         from_bool8(self.0.isBackendAsynchronous)
     }
 
+    /// Returns true if the backend of this context supports `DeviceType::Loopback` devices.
+    pub fn is_loopback_supported(&self) -> bool {
+        from_bool32(unsafe { sys::ma_context_is_loopback_supported(&self.0 as *const _ as *mut _) })
+    }
+
     /// Retrieves basic information about every active playback and capture device. This function
     /// will allocate memory internally for device lists.
     /// This function will not call the closure if an error occurred.
@@ -1185,6 +1553,45 @@ impl Context {
     pub fn new(backends: &[Backend], config: Option<&ContextConfig>) -> Result<Context, Error> {
         RawContext::alloc(backends, config).map(Context)
     }
+
+    /// Returns all of the information about a device, including its native data formats and
+    /// whether or not it is the default device. `DeviceType::Loopback` devices are looked up among
+    /// the playback devices.
+    pub fn device_info(
+        &self,
+        device_type: DeviceType,
+        device_id: &DeviceId,
+        share_mode: ShareMode,
+    ) -> Result<DeviceInfo, Error> {
+        let mut device_info = self.get_device_info(device_type, device_id, share_mode)?;
+
+        // NOTE: Not every backend reports whether a device is the default one when it is queried
+        // on its own, but they do when enumerating, so the enumerated devices are checked as well.
+        if !device_info.is_default() {
+            let mut is_default = false;
+            let mut check = |devices: &[DeviceIdAndName]| {
+                is_default |= devices
+                    .iter()
+                    .any(|device| device.is_default() && device.id() == device_id);
+            };
+
+            let result = match device_type {
+                DeviceType::Playback | DeviceType::Loopback => self.with_playback_devices(check),
+                DeviceType::Capture => self.with_capture_devices(check),
+                DeviceType::Duplex => self.with_devices(|playback, capture| {
+                    check(playback);
+                    check(capture);
+                }),
+            };
+
+            // Failing to enumerate just means that the device can't be detected as the default.
+            if result.is_ok() {
+                device_info.0.isDefault = to_bool32(is_default);
+            }
+        }
+
+        Ok(device_info)
+    }
 }
 
 impl std::ops::Deref for Context {
@@ -1234,14 +1641,22 @@ impl RawDevice {
                     .map(|f| (f)())
             });
 
+            let state_callback = Mutex::new(unsafe {
+                ((*config_user_data).state_callback_factory)
+                    .as_ref()
+                    .map(|f| (f)())
+            });
+
             self.0.pUserData = Box::into_raw(Box::new(DeviceUserData {
                 data_callback,
                 stop_callback,
+                state_callback,
             })) as *mut _;
         } else {
             self.0.pUserData = Box::into_raw(Box::new(DeviceUserData {
                 data_callback: MaybePoisoned::CanUse(None),
                 stop_callback: MaybePoisoned::CanUse(None),
+                state_callback: Mutex::new(None),
             })) as *mut _;
         }
     }
@@ -1262,6 +1677,28 @@ impl RawDevice {
         std::mem::forget(user_data);
     }
 
+    fn set_raw_state_callback(&mut self, callback: Option<BoxedStateCallback>) {
+        assert!(!self.0.pUserData.is_null());
+        let mut user_data =
+            unsafe { Box::<DeviceUserData>::from_raw(self.0.pUserData as *mut DeviceUserData) };
+        user_data.state_callback = Mutex::new(callback);
+        std::mem::forget(user_data);
+    }
+
+    /// Calls the state callback (if there is one) with the new state of this device.
+    fn notify_state(&self, state: DeviceState) {
+        let user_data = self.0.pUserData.cast::<DeviceUserData>();
+        if user_data.is_null() {
+            return;
+        }
+
+        if let Ok(mut state_callback) = unsafe { (*user_data).state_callback.lock() } {
+            if let Some(ref mut state_callback) = *state_callback {
+                (state_callback)(self, state);
+            }
+        }
+    }
+
     /// This will return the context **owned** by this device. A context that was passed into this
     /// device via `new` is **not** owned by this device and if you need a reference to that use
     /// `context` instead.
@@ -1324,6 +1761,33 @@ impl RawDevice {
         from_bool32(unsafe { sys::ma_device_is_started(&self.0 as *const _ as *mut _) })
     }
 
+    /// Returns the current state of this device.
+    #[inline]
+    pub fn state(&self) -> DeviceState {
+        DeviceState::from_c(unsafe { sys::ma_device_get_state(&self.0) })
+    }
+
+    /// Blocks until this device is `Stopped` (or `Uninitialized`) or until `timeout` has passed,
+    /// returning the state that the device ended up in. This is meant for restarting or closing a
+    /// device after the stop callback was called, see `Device::set_state_callback`. If the device
+    /// isn't actually stopping this waits for the full `timeout` and returns the current state.
+    ///
+    /// **WARNING** This should not be called from a callback, because the device can't finish
+    /// stopping until the callbacks return.
+    pub fn wait_until_stopped(&self, timeout: std::time::Duration) -> DeviceState {
+        let wait_start = std::time::Instant::now();
+        loop {
+            let state = self.state();
+            if state == DeviceState::Stopped
+                || state == DeviceState::Uninitialized
+                || wait_start.elapsed() >= timeout
+            {
+                return state;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+    }
+
     /// Sets the master volume factor for the device.
     ///
     /// The volume factor must be between 0 (silence) and 1 (full volume). Use `set_master_gain_db()` to use decibel notation, where 0 is full volume and
@@ -1447,7 +1911,27 @@ unsafe impl Sync for RawDevice {}
 pub struct Device(Arc<RawDevice>);
 
 impl Device {
+    /// Creates a new device. If the config is for a `DeviceType::Loopback` device and the backend
+    /// does not support loopback, this will fail with `Error::DeviceTypeNotSupported`.
     pub fn new(context: Option<Context>, config: &DeviceConfig) -> Result<Device, Error> {
+        if config.device_type() != DeviceType::Loopback {
+            return RawDevice::alloc(context, config).map(Device);
+        }
+
+        let supported = match context {
+            Some(ref context) => context.is_loopback_supported(),
+
+            // NOTE: Without a context miniaudio creates one using the first of the enabled backends
+            // that works, so loopback can only work if one of them supports it.
+            None => enabled_backends()
+                .into_iter()
+                .any(Backend::supports_loopback),
+        };
+
+        if !supported {
+            return Err(Error::DeviceTypeNotSupported);
+        }
+
         RawDevice::alloc(context, config).map(Device)
     }
 
@@ -1494,21 +1978,83 @@ impl Device {
             .set_raw_stop_callback(Some(Box::new(callback)));
     }
 
+    /// Override the state callback of the device with a different one.
+    ///
+    /// The state callback is called with:
+    /// * `DeviceState::Started` after the device was started with `start`.
+    /// * `DeviceState::Stopping` while the device is being stopped with `stop`, from the same
+    ///   thread and right after the stop callback.
+    /// * `DeviceState::Stopped` after the device was stopped with `stop`.
+    ///
+    /// **IMPORTANT** When the backend stops the device on its own (e.g. when it is unplugged) the
+    /// state callback is not called at all. miniaudio keeps the device `Started` until the stop
+    /// callback has returned and then marks it as `Stopped` without notifying anyone. Use the stop
+    /// callback to find out about such stops, and wait for the stop to finish from another thread
+    /// with `RawDevice::wait_until_stopped` (or by polling `RawDevice::state`) before starting or
+    /// closing the device again.
+    ///
+    /// ### Panics
+    ///
+    /// * This will panic if it is called after the device has been started.
+    /// * This will also panic if there is more than one reference to the same device (if this has
+    ///   been cloned).
+    pub fn set_state_callback<F>(&mut self, callback: F)
+    where
+        F: FnMut(&RawDevice, DeviceState) + Send + 'static,
+    {
+        if self.is_started() {
+            panic!("cannot set the state callback after the device has been started");
+        }
+
+        Arc::get_mut(&mut self.0)
+            .expect("cannot set state callback while there is more than one reference to a device")
+            .set_raw_state_callback(Some(Box::new(callback)));
+    }
+
     /// Starts the device. For playback devices this begins playback. For capture devices this
     /// begins recording.
     /// Use `stop` to stop this device.
     ///
+    /// This fails with `DeviceControlError::InvalidState` unless the device is `Stopped`, which
+    /// includes while a previous stop is still in progress (see `RawDevice::wait_until_stopped`).
+    ///
     /// **WARNING** This should not be called from a callback.
-    pub fn start(&self) -> Result<(), Error> {
-        unsafe { self.0.raw_start() }
+    pub fn start(&self) -> Result<(), DeviceControlError> {
+        match self.state() {
+            DeviceState::Stopped => {}
+            state => return Err(DeviceControlError::InvalidState(state)),
+        }
+
+        unsafe { self.0.raw_start() }.map_err(|err| self.control_error(err))?;
+        self.0.notify_state(DeviceState::Started);
+        Ok(())
     }
 
     /// Stops this device. For playback devices this stops playback. For capture devices this stops
     /// recording. Use `start` to start this device again.
     ///
+    /// This fails with `DeviceControlError::InvalidState` unless the device is `Started`.
+    ///
     /// **WARNING** This should not be called from a callback.
-    pub fn stop(&self) -> Result<(), Error> {
-        unsafe { self.0.raw_stop() }
+    pub fn stop(&self) -> Result<(), DeviceControlError> {
+        match self.state() {
+            DeviceState::Started => {}
+            state => return Err(DeviceControlError::InvalidState(state)),
+        }
+
+        unsafe { self.0.raw_stop() }.map_err(|err| self.control_error(err))?;
+        self.0.notify_state(DeviceState::Stopped);
+        Ok(())
+    }
+
+    /// The state can change between checking it and calling into miniaudio, in which case
+    /// miniaudio reports an invalid operation and the new state is reported instead.
+    fn control_error(&self, err: Error) -> DeviceControlError {
+        if err == Error::InvalidOperation {
+            DeviceControlError::InvalidState(self.state())
+        } else {
+            DeviceControlError::Backend(err)
+        }
     }
 }
 
@@ -1555,11 +2101,18 @@ impl DeviceCapture {
         self.0.channels
     }
 
-    pub fn channel_map(&self) -> &[Channel; MAX_CHANNELS] {
-        unsafe {
-            &*(&self.0.channelMap as *const [sys::ma_channel; MAX_CHANNELS]
-                as *const [Channel; MAX_CHANNELS])
-        }
+    pub fn channel_map(&self) -> ChannelMap {
+        ChannelMap::from_raw(&self.0.channelMap, self.0.channels)
+    }
+
+    #[inline]
+    pub(crate) fn internal_sample_rate(&self) -> u32 {
+        self.0.internalSampleRate
+    }
+
+    #[inline]
+    pub(crate) fn internal_period_size_in_frames(&self) -> u32 {
+        self.0.internalPeriodSizeInFrames
     }
 }
 
@@ -1598,11 +2151,8 @@ impl DevicePlayback {
         self.0.channels
     }
 
-    pub fn channel_map(&self) -> &[Channel; MAX_CHANNELS] {
-        unsafe {
-            &*(&self.0.channelMap as *const [sys::ma_channel; MAX_CHANNELS]
-                as *const [Channel; MAX_CHANNELS])
-        }
+    pub fn channel_map(&self) -> ChannelMap {
+        ChannelMap::from_raw(&self.0.channelMap, self.0.channels)
     }
 
     // FIXME I'm not sure if these are supposed to be public.
@@ -0,0 +1,292 @@
+use crate::base::*;
+use crate::frames::Frames;
+use miniaudio_sys as sys;
+use std::ffi::CString;
+use std::io;
+use std::mem::MaybeUninit;
+use std::ops::{Deref, DerefMut};
+use std::path::Path;
+
+/// The container/codec that an encoder will write its output in.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ResourceFormat {
+    #[default]
+    Wav = sys::ma_resource_format_wav as _,
+}
+impl_from_c!(ResourceFormat, sys::ma_resource_format);
+
+#[repr(transparent)]
+#[derive(Clone)]
+pub struct EncoderConfig(sys::ma_encoder_config);
+
+impl EncoderConfig {
+    #[inline]
+    pub fn new(
+        resource_format: ResourceFormat,
+        format: Format,
+        channels: u32,
+        sample_rate: u32,
+    ) -> Self {
+        EncoderConfig(unsafe {
+            sys::ma_encoder_config_init(resource_format as _, format as _, channels, sample_rate)
+        })
+    }
+
+    #[inline]
+    pub fn resource_format(&self) -> ResourceFormat {
+        ResourceFormat::from_c(self.0.resourceFormat)
+    }
+
+    #[inline]
+    pub fn set_resource_format(&mut self, resource_format: ResourceFormat) {
+        self.0.resourceFormat = resource_format as _;
+    }
+
+    #[inline]
+    pub fn format(&self) -> Format {
+        Format::from_c(self.0.format)
+    }
+
+    #[inline]
+    pub fn set_format(&mut self, format: Format) {
+        self.0.format = format as _;
+    }
+
+    #[inline]
+    pub fn channels(&self) -> u32 {
+        self.0.channels
+    }
+
+    #[inline]
+    pub fn set_channels(&mut self, channels: u32) {
+        self.0.channels = channels;
+    }
+
+    #[inline]
+    pub fn sample_rate(&self) -> u32 {
+        self.0.sampleRate
+    }
+
+    #[inline]
+    pub fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.0.sampleRate = sample_rate;
+    }
+}
+
+#[repr(transparent)]
+pub struct RawEncoder {
+    inner: sys::ma_encoder,
+}
+
+impl RawEncoder {
+    /// Encodes the given frames and writes them to the output, returning the number of frames
+    /// that were written.
+    #[inline]
+    pub fn write_pcm_frames(&mut self, input: &Frames) -> u64 {
+        assert!(
+            input.format() == self.format(),
+            "input and encoder format did not match (input: {:?}, encoder: {:?})",
+            input.format(),
+            self.format()
+        );
+
+        assert!(
+            input.channels() == self.channels(),
+            "input and encoder channel count did not match (input: {}, encoder: {})",
+            input.channels(),
+            self.channels()
+        );
+
+        unsafe {
+            sys::ma_encoder_write_pcm_frames(
+                &mut self.inner,
+                input.as_ptr() as *const _,
+                input.frame_count() as u64,
+            )
+        }
+    }
+
+    #[inline]
+    pub fn config(&self) -> &EncoderConfig {
+        unsafe { &*(&self.inner.config as *const sys::ma_encoder_config as *const EncoderConfig) }
+    }
+
+    #[inline]
+    pub fn resource_format(&self) -> ResourceFormat {
+        self.config().resource_format()
+    }
+
+    #[inline]
+    pub fn format(&self) -> Format {
+        self.config().format()
+    }
+
+    #[inline]
+    pub fn channels(&self) -> u32 {
+        self.config().channels()
+    }
+
+    #[inline]
+    pub fn sample_rate(&self) -> u32 {
+        self.config().sample_rate()
+    }
+}
+
+impl Drop for RawEncoder {
+    fn drop(&mut self) {
+        unsafe { sys::ma_encoder_uninit(&mut self.inner) };
+    }
+}
+
+pub struct Encoder {
+    inner: Box<RawEncoder>,
+
+    // NOTE: This must be declared after `inner` so that it is dropped after the encoder has been
+    // uninitialized. Uninitializing an encoder will seek back and finalize headers using the
+    // writer, which is why the writer is owned here instead of being freed in a `Drop` impl like the
+    // reader of a `Decoder`.
+    _writer: Option<BoxedWriter>,
+}
+
+impl Encoder {
+    /// Creates an `Encoder` that writes to a file. This will fail with an `InvalidFile` error if
+    /// the path is not valid utf-8.
+    pub fn from_file<P: AsRef<Path>>(file: P, config: &EncoderConfig) -> Result<Self, Error> {
+        let encoder = Box::new(MaybeUninit::<RawEncoder>::uninit());
+        let filename = file
+            .as_ref()
+            .to_str()
+            .ok_or(Error::InvalidFile)
+            .and_then(|s| CString::new(s.to_string()).map_err(|_err| Error::InvalidFile))?;
+
+        let result = unsafe {
+            sys::ma_encoder_init_file(
+                filename.as_ptr() as *const _,
+                &config.0 as *const _,
+                encoder.as_ptr() as *mut _,
+            )
+        };
+
+        map_result!(
+            result,
+            Encoder {
+                inner: unsafe {
+                    std::mem::transmute::<Box<MaybeUninit<RawEncoder>>, Box<RawEncoder>>(encoder)
+                },
+                _writer: None,
+            }
+        )
+    }
+
+    pub fn from_write<T: 'static + SeekWrite>(
+        writer: T,
+        config: &EncoderConfig,
+    ) -> Result<Self, Error> {
+        Self::from_boxed_write(Box::new(writer), config)
+    }
+
+    pub fn from_boxed_write(
+        writer: Box<dyn SeekWrite>,
+        config: &EncoderConfig,
+    ) -> Result<Self, Error> {
+        let encoder = Box::new(MaybeUninit::<RawEncoder>::uninit());
+        let writer = BoxedWriter(Box::into_raw(Box::new(writer)));
+
+        let result = unsafe {
+            sys::ma_encoder_init(
+                Some(encoder_write_with_writer),
+                Some(encoder_seek_with_writer),
+                writer.0 as *mut _,
+                &config.0 as *const _,
+                encoder.as_ptr() as *mut _,
+            )
+        };
+
+        // If initialization failed the writer will just be dropped here.
+        map_result!(
+            result,
+            Encoder {
+                inner: unsafe {
+                    std::mem::transmute::<Box<MaybeUninit<RawEncoder>>, Box<RawEncoder>>(encoder)
+                },
+                _writer: Some(writer),
+            }
+        )
+    }
+}
+
+impl Deref for Encoder {
+    type Target = RawEncoder;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl DerefMut for Encoder {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.inner
+    }
+}
+
+unsafe impl Send for Encoder {}
+unsafe impl Sync for Encoder {}
+
+pub trait SeekWrite: io::Write + io::Seek {}
+
+// Create a blanket implementation so that everything that implements both
+// io::Write and io::Seek also implements SeekWrite.
+impl<T> SeekWrite for T where T: io::Write + io::Seek {}
+
+/// Owns the writer that is passed to miniaudio as user data.
+struct BoxedWriter(*mut Box<dyn SeekWrite>);
+
+impl Drop for BoxedWriter {
+    fn drop(&mut self) {
+        // Recreate the box and allow it to be dropped.
+        let _writer: Box<Box<dyn SeekWrite>> = unsafe { Box::from_raw(self.0) };
+    }
+}
+
+unsafe extern "C" fn encoder_write_with_writer(
+    encoder: *mut sys::ma_encoder,
+    buffer_in: *const std::ffi::c_void,
+    bytes_to_write: usize,
+) -> usize {
+    if encoder.is_null() {
+        return 0;
+    }
+
+    let writer: &mut Box<dyn SeekWrite> = &mut *((*encoder).pUserData as *mut _);
+    let buffer = std::slice::from_raw_parts(buffer_in as *const u8, bytes_to_write);
+
+    // miniaudio treats a short write as an error, so we try to write everything here.
+    if writer.write_all(buffer).is_ok() {
+        bytes_to_write
+    } else {
+        0
+    }
+}
+
+unsafe extern "C" fn encoder_seek_with_writer(
+    encoder: *mut sys::ma_encoder,
+    byte_offset: std::os::raw::c_int,
+    origin: sys::ma_seek_origin,
+) -> sys::ma_bool32 {
+    if encoder.is_null() {
+        return to_bool32(false);
+    }
+
+    let writer: &mut Box<dyn SeekWrite> = &mut *((*encoder).pUserData as *mut _);
+    let pos = match origin {
+        sys::ma_seek_origin_start => io::SeekFrom::Start(byte_offset as _),
+        sys::ma_seek_origin_current => io::SeekFrom::Current(byte_offset as _),
+        sys::ma_seek_origin_end => io::SeekFrom::End(byte_offset as _),
+
+        // Unwinding into C is undefined behavior so we just report a failed seek here.
+        _ => return to_bool32(false),
+    };
+
+    to_bool32(writer.seek(pos).is_ok())
+}
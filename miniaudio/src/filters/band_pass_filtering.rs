@@ -1,5 +1,5 @@
 use super::biquad_filtering::Biquad;
-use super::Filter;
+use super::{BiquadCoefficients, Filter, FrequencyResponse};
 use crate::base::{Error, Format, MAX_FILTER_ORDER};
 use crate::frames::{Frames, FramesMut};
 use miniaudio_sys as sys;
@@ -115,6 +115,12 @@ impl BPF2 {
     }
 }
 
+impl FrequencyResponse for BPF2 {
+    fn coefficients(&self) -> Vec<BiquadCoefficients> {
+        self.bq().coefficients()
+    }
+}
+
 impl Filter for BPF2 {
     #[inline]
     fn process_pcm_frames(&mut self, output: &mut FramesMut, input: &Frames) -> Result<(), Error> {
@@ -129,6 +135,21 @@ impl Filter for BPF2 {
             )
         })
     }
+
+    #[inline]
+    fn process_pcm_frames_in_place(&mut self, frames: &mut FramesMut) -> Result<(), Error> {
+        let frame_count = frames.frame_count() as u64;
+        let frames_ptr = frames.as_mut_ptr();
+
+        Error::from_c_result(unsafe {
+            sys::ma_bpf2_process_pcm_frames(
+                &mut self.0 as *mut _,
+                frames_ptr as *mut _,
+                frames_ptr as *const _,
+                frame_count,
+            )
+        })
+    }
 }
 
 #[repr(transparent)]
@@ -161,7 +182,7 @@ impl BPFConfig {
     }
 
     #[inline]
-    pub fn set_format(&mut self, format: u32) {
+    pub fn set_format(&mut self, format: Format) {
         self.0.format = format as _;
     }
 
@@ -259,6 +280,15 @@ impl BPF {
     }
 }
 
+impl FrequencyResponse for BPF {
+    fn coefficients(&self) -> Vec<BiquadCoefficients> {
+        self.bpf2()[..self.bpf2_count() as usize]
+            .iter()
+            .flat_map(|bpf2| bpf2.coefficients())
+            .collect()
+    }
+}
+
 impl Filter for BPF {
     #[inline]
     fn process_pcm_frames(&mut self, output: &mut FramesMut, input: &Frames) -> Result<(), Error> {
@@ -273,4 +303,19 @@ impl Filter for BPF {
             )
         })
     }
+
+    #[inline]
+    fn process_pcm_frames_in_place(&mut self, frames: &mut FramesMut) -> Result<(), Error> {
+        let frame_count = frames.frame_count() as u64;
+        let frames_ptr = frames.as_mut_ptr();
+
+        Error::from_c_result(unsafe {
+            sys::ma_bpf_process_pcm_frames(
+                &mut self.0 as *mut _,
+                frames_ptr as *mut _,
+                frames_ptr as *const _,
+                frame_count,
+            )
+        })
+    }
 }
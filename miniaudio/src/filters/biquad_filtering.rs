@@ -1,4 +1,4 @@
-use super::Filter;
+use super::{BiquadCoefficients, Filter, FrequencyResponse};
 use crate::base::*;
 use crate::frames::{Frames, FramesMut};
 use miniaudio_sys as sys;
@@ -94,6 +94,10 @@ impl BiquadConfig {
     }
 }
 
+/// The number of fractional bits used by miniaudio for the coefficients of fixed-point (s16)
+/// biquads. This is only defined in the implementation section of miniaudio.h.
+const BIQUAD_FIXED_POINT_SHIFT: u32 = 14;
+
 #[repr(transparent)]
 #[derive(Clone)]
 pub struct Biquad(sys::ma_biquad);
@@ -124,11 +128,53 @@ impl Biquad {
         self.0.channels
     }
 
-    // FIXME the pointer is not marked as const in the C source even though it could be. I should
-    // probably try to get that fixed.
+    // NOTE: The pointer is not marked as const in the C source even though the biquad is never
+    // modified, so we cast it here like the other filters do.
     #[inline]
-    pub fn latency(&mut self) -> u32 {
-        unsafe { sys::ma_biquad_get_latency(&mut self.0) }
+    pub fn latency(&self) -> u32 {
+        unsafe { sys::ma_biquad_get_latency(&self.0 as *const _ as *mut _) }
+    }
+
+    /// Processes frames through raw pointers so that `output` and `input` are allowed to point to
+    /// the same frames for processing in place.
+    #[inline]
+    pub(crate) unsafe fn process_pcm_frames_raw(
+        &mut self,
+        output: *mut u8,
+        input: *const u8,
+        frame_count: u64,
+    ) -> Result<(), Error> {
+        Error::from_c_result(sys::ma_biquad_process_pcm_frames(
+            &mut self.0,
+            output as *mut _,
+            input as *const _,
+            frame_count,
+        ))
+    }
+}
+
+/// Converts a coefficient of a filter with the given format to a float. Fixed-point coefficients
+/// are only used for `Format::S16`.
+pub(crate) fn coefficient_to_f64(format: Format, coefficient: &sys::ma_biquad_coefficient) -> f64 {
+    unsafe {
+        if format == Format::F32 {
+            coefficient.f32_ as f64
+        } else {
+            coefficient.s32 as f64 / (1 << BIQUAD_FIXED_POINT_SHIFT) as f64
+        }
+    }
+}
+
+impl FrequencyResponse for Biquad {
+    fn coefficients(&self) -> Vec<BiquadCoefficients> {
+        let format = self.format();
+        vec![BiquadCoefficients {
+            b0: coefficient_to_f64(format, &self.0.b0),
+            b1: coefficient_to_f64(format, &self.0.b1),
+            b2: coefficient_to_f64(format, &self.0.b2),
+            a1: coefficient_to_f64(format, &self.0.a1),
+            a2: coefficient_to_f64(format, &self.0.a2),
+        }]
     }
 }
 
@@ -147,4 +193,19 @@ impl Filter for Biquad {
         };
         Error::from_c_result(result)
     }
+
+    #[inline]
+    fn process_pcm_frames_in_place(&mut self, frames: &mut FramesMut) -> Result<(), Error> {
+        let frame_count = frames.frame_count() as u64;
+        let frames_ptr = frames.as_mut_ptr();
+
+        Error::from_c_result(unsafe {
+            sys::ma_biquad_process_pcm_frames(
+                &mut self.0 as *mut _,
+                frames_ptr as *mut _,
+                frames_ptr as *const _,
+                frame_count,
+            )
+        })
+    }
 }
@@ -0,0 +1,204 @@
+use super::Filter;
+use crate::base::{Error, Format};
+use crate::frames::{Frames, FramesMut};
+
+struct FilterStage {
+    filter: Box<dyn Filter + Send>,
+    bypass: bool,
+}
+
+/// Runs frames through a list of filters one after the other. The intermediate results are
+/// written to a scratch buffer owned by the chain, which grows to fit the largest period that has
+/// been processed and is never shrunk. Use `reserve_frames` up front to avoid allocating on the
+/// audio thread.
+///
+/// Since a `FilterChain` is itself a `Filter`, chains can be nested.
+#[derive(Default)]
+pub struct FilterChain {
+    stages: Vec<FilterStage>,
+
+    // NOTE: This is stored as `u32` so that the scratch frames are aligned for every format.
+    scratch: Vec<u32>,
+}
+
+impl FilterChain {
+    pub fn new() -> FilterChain {
+        FilterChain::default()
+    }
+
+    /// Makes sure that the scratch buffer can hold at least `frame_count` frames with the given
+    /// format and channel count without allocating while processing.
+    pub fn reserve_frames(&mut self, format: Format, channels: u32, frame_count: usize) {
+        let byte_count = format.size_in_bytes() * channels as usize * frame_count;
+        self.ensure_scratch(byte_count);
+    }
+
+    /// Adds a filter after all of the existing stages, returning its index.
+    pub fn push<F: 'static + Filter + Send>(&mut self, filter: F) -> usize {
+        self.push_boxed(Box::new(filter))
+    }
+
+    /// Adds a filter after all of the existing stages, returning its index.
+    pub fn push_boxed(&mut self, filter: Box<dyn Filter + Send>) -> usize {
+        self.stages.push(FilterStage {
+            filter,
+            bypass: false,
+        });
+        self.stages.len() - 1
+    }
+
+    /// Inserts a filter at `index`, shifting all of the stages after it. This will panic if
+    /// `index > stage_count()`.
+    pub fn insert<F: 'static + Filter + Send>(&mut self, index: usize, filter: F) {
+        self.insert_boxed(index, Box::new(filter))
+    }
+
+    /// Inserts a filter at `index`, shifting all of the stages after it. This will panic if
+    /// `index > stage_count()`.
+    pub fn insert_boxed(&mut self, index: usize, filter: Box<dyn Filter + Send>) {
+        self.stages.insert(
+            index,
+            FilterStage {
+                filter,
+                bypass: false,
+            },
+        );
+    }
+
+    /// Removes the stage at `index`, returning its filter. This will panic if `index` is out of
+    /// bounds.
+    pub fn remove(&mut self, index: usize) -> Box<dyn Filter + Send> {
+        self.stages.remove(index).filter
+    }
+
+    #[inline]
+    pub fn stage_count(&self) -> usize {
+        self.stages.len()
+    }
+
+    #[inline]
+    pub fn stage(&self, index: usize) -> Option<&(dyn Filter + Send)> {
+        self.stages.get(index).map(|stage| &*stage.filter)
+    }
+
+    #[inline]
+    pub fn stage_mut(&mut self, index: usize) -> Option<&mut (dyn Filter + Send + 'static)> {
+        self.stages.get_mut(index).map(|stage| &mut *stage.filter)
+    }
+
+    /// Returns true if the stage at `index` is bypassed or false if it is not. This will return
+    /// `None` if `index` is out of bounds.
+    #[inline]
+    pub fn is_bypassed(&self, index: usize) -> Option<bool> {
+        self.stages.get(index).map(|stage| stage.bypass)
+    }
+
+    /// Enables or disables the stage at `index`. A bypassed stage keeps its state but no frames
+    /// are passed through it. This will fail with `InvalidArgs` if `index` is out of bounds.
+    pub fn set_bypass(&mut self, index: usize, bypass: bool) -> Result<(), Error> {
+        let stage = self.stages.get_mut(index).ok_or(Error::InvalidArgs)?;
+        stage.bypass = bypass;
+        Ok(())
+    }
+
+    fn active_count(&self) -> usize {
+        self.stages.iter().filter(|stage| !stage.bypass).count()
+    }
+
+    fn ensure_scratch(&mut self, byte_count: usize) {
+        let len = byte_count.div_ceil(std::mem::size_of::<u32>());
+        if self.scratch.len() < len {
+            self.scratch.resize(len, 0);
+        }
+    }
+}
+
+impl Filter for FilterChain {
+    /// Runs `input` through every stage that is not bypassed and writes the result to `output`.
+    ///
+    /// **WARNING** If the scratch buffer is too small for `output` it is grown here, which
+    /// allocates. Call `reserve_frames` with the largest period up front to avoid allocating on
+    /// the audio thread.
+    fn process_pcm_frames(&mut self, output: &mut FramesMut, input: &Frames) -> Result<(), Error> {
+        super::ensure_frames_compat(output, input)?;
+
+        let active_count = self.active_count();
+        if active_count == 0 {
+            output.as_bytes_mut().copy_from_slice(input.as_bytes());
+            return Ok(());
+        }
+
+        self.ensure_scratch(output.byte_count());
+        let (format, channels) = (output.format(), output.channels());
+        let scratch = scratch_bytes(&mut self.scratch, output.byte_count());
+
+        // The first stage reads from `input`, after which the stages alternate between the scratch
+        // buffer and `output`. The first stage writes to whichever one makes the last stage write
+        // to `output`.
+        let mut stages = self.stages.iter_mut().filter(|stage| !stage.bypass);
+        let first = stages.next().expect("no active stages");
+        let mut in_scratch = active_count.is_multiple_of(2);
+        if in_scratch {
+            let mut scratch_frames = FramesMut::wrap::<u8>(scratch, format, channels);
+            first
+                .filter
+                .process_pcm_frames(&mut scratch_frames, input)?;
+        } else {
+            first.filter.process_pcm_frames(output, input)?;
+        }
+
+        for stage in stages {
+            if in_scratch {
+                let input = Frames::wrap::<u8>(scratch, format, channels);
+                stage.filter.process_pcm_frames(output, &input)?;
+            } else {
+                let input = Frames::wrap::<u8>(output.as_bytes(), format, channels);
+                let mut output = FramesMut::wrap::<u8>(scratch, format, channels);
+                stage.filter.process_pcm_frames(&mut output, &input)?;
+            }
+            in_scratch = !in_scratch;
+        }
+
+        Ok(())
+    }
+
+    /// Like `process_pcm_frames` but replaces `frames` with the result. Unlike the other filters
+    /// this can allocate, see `process_pcm_frames`.
+    fn process_pcm_frames_in_place(&mut self, frames: &mut FramesMut) -> Result<(), Error> {
+        let active_count = self.active_count();
+        if active_count == 0 {
+            return Ok(());
+        }
+
+        self.ensure_scratch(frames.byte_count());
+        let (format, channels) = (frames.format(), frames.channels());
+        let scratch = scratch_bytes(&mut self.scratch, frames.byte_count());
+
+        // The stages alternate between writing to the scratch buffer and writing to `frames`, so
+        // with an odd number of stages the frames are first copied into the scratch buffer to make
+        // sure that the last stage writes to `frames`.
+        let mut in_scratch = !active_count.is_multiple_of(2);
+        if in_scratch {
+            scratch.copy_from_slice(frames.as_bytes());
+        }
+
+        for stage in self.stages.iter_mut().filter(|stage| !stage.bypass) {
+            if in_scratch {
+                let input = Frames::wrap::<u8>(scratch, format, channels);
+                stage.filter.process_pcm_frames(frames, &input)?;
+            } else {
+                let input = Frames::wrap::<u8>(frames.as_bytes(), format, channels);
+                let mut output = FramesMut::wrap::<u8>(scratch, format, channels);
+                stage.filter.process_pcm_frames(&mut output, &input)?;
+            }
+            in_scratch = !in_scratch;
+        }
+
+        Ok(())
+    }
+}
+
+fn scratch_bytes(scratch: &mut [u32], byte_count: usize) -> &mut [u8] {
+    assert!(std::mem::size_of_val(scratch) >= byte_count);
+    unsafe { std::slice::from_raw_parts_mut(scratch.as_mut_ptr() as *mut u8, byte_count) }
+}
@@ -1,6 +1,6 @@
-use super::biquad_filtering::Biquad;
-use super::Filter;
-use crate::base::{Error, Format};
+use super::biquad_filtering::{coefficient_to_f64, Biquad};
+use super::{BiquadCoefficients, Filter, FrequencyResponse};
+use crate::base::{Error, Format, MAX_FILTER_ORDER};
 use crate::frames::{Frames, FramesMut};
 use miniaudio_sys as sys;
 
@@ -75,7 +75,7 @@ impl HPF1Config {
 /// Configuration for a second order high-pass filter.
 #[repr(transparent)]
 #[derive(Clone)]
-pub struct HPF2Config(sys::ma_hpf1_config);
+pub struct HPF2Config(sys::ma_hpf2_config);
 
 impl HPF2Config {
     pub fn new(
@@ -169,6 +169,20 @@ impl HPF1 {
     }
 }
 
+impl FrequencyResponse for HPF1 {
+    fn coefficients(&self) -> Vec<BiquadCoefficients> {
+        // miniaudio applies this as `y = a*x - (1 - a)*y[n-1]`.
+        let a = coefficient_to_f64(Format::from_c(self.0.format), &self.0.a);
+        vec![BiquadCoefficients {
+            b0: a,
+            b1: 0.0,
+            b2: 0.0,
+            a1: 1.0 - a,
+            a2: 0.0,
+        }]
+    }
+}
+
 impl Filter for HPF1 {
     #[inline]
     fn process_pcm_frames(&mut self, output: &mut FramesMut, input: &Frames) -> Result<(), Error> {
@@ -183,6 +197,21 @@ impl Filter for HPF1 {
             )
         })
     }
+
+    #[inline]
+    fn process_pcm_frames_in_place(&mut self, frames: &mut FramesMut) -> Result<(), Error> {
+        let frame_count = frames.frame_count() as u64;
+        let frames_ptr = frames.as_mut_ptr();
+
+        Error::from_c_result(unsafe {
+            sys::ma_hpf1_process_pcm_frames(
+                &mut self.0 as *mut _,
+                frames_ptr as *mut _,
+                frames_ptr as *const _,
+                frame_count,
+            )
+        })
+    }
 }
 
 /// Second order high-pass filter.
@@ -212,11 +241,21 @@ impl HPF2 {
         unsafe { (&self.0.bq as *const _ as *const Biquad).as_ref().unwrap() }
     }
 
+    pub(crate) fn biquad_mut(&mut self) -> &mut Biquad {
+        unsafe { &mut *(&mut self.0.bq as *mut sys::ma_biquad as *mut Biquad) }
+    }
+
     pub fn latency(&self) -> u32 {
         unsafe { sys::ma_hpf2_get_latency(&self.0 as *const _ as *mut _) }
     }
 }
 
+impl FrequencyResponse for HPF2 {
+    fn coefficients(&self) -> Vec<BiquadCoefficients> {
+        self.biquad().coefficients()
+    }
+}
+
 impl Filter for HPF2 {
     #[inline]
     fn process_pcm_frames(&mut self, output: &mut FramesMut, input: &Frames) -> Result<(), Error> {
@@ -231,6 +270,21 @@ impl Filter for HPF2 {
             )
         })
     }
+
+    #[inline]
+    fn process_pcm_frames_in_place(&mut self, frames: &mut FramesMut) -> Result<(), Error> {
+        let frame_count = frames.frame_count() as u64;
+        let frames_ptr = frames.as_mut_ptr();
+
+        Error::from_c_result(unsafe {
+            sys::ma_hpf2_process_pcm_frames(
+                &mut self.0 as *mut _,
+                frames_ptr as *mut _,
+                frames_ptr as *const _,
+                frame_count,
+            )
+        })
+    }
 }
 
 /// Configuration for a high-pass filter with configurable order (up to 8)
@@ -331,6 +385,25 @@ impl HPF {
     }
 }
 
+impl FrequencyResponse for HPF {
+    fn coefficients(&self) -> Vec<BiquadCoefficients> {
+        // The first order section is applied before the second order sections.
+        let hpf1 = unsafe { &*(&self.0.hpf1 as *const [sys::ma_hpf1; 1] as *const [HPF1; 1]) };
+        let hpf2 = unsafe {
+            &*(&self.0.hpf2 as *const [sys::ma_hpf2; MAX_FILTER_ORDER / 2]
+                as *const [HPF2; MAX_FILTER_ORDER / 2])
+        };
+
+        let first_order = hpf1[..self.0.hpf1Count as usize]
+            .iter()
+            .flat_map(|hpf1| hpf1.coefficients());
+        let second_order = hpf2[..self.0.hpf2Count as usize]
+            .iter()
+            .flat_map(|hpf2| hpf2.coefficients());
+        first_order.chain(second_order).collect()
+    }
+}
+
 impl Filter for HPF {
     #[inline]
     fn process_pcm_frames(&mut self, output: &mut FramesMut, input: &Frames) -> Result<(), Error> {
@@ -345,4 +418,19 @@ impl Filter for HPF {
             )
         })
     }
+
+    #[inline]
+    fn process_pcm_frames_in_place(&mut self, frames: &mut FramesMut) -> Result<(), Error> {
+        let frame_count = frames.frame_count() as u64;
+        let frames_ptr = frames.as_mut_ptr();
+
+        Error::from_c_result(unsafe {
+            sys::ma_hpf_process_pcm_frames(
+                &mut self.0 as *mut _,
+                frames_ptr as *mut _,
+                frames_ptr as *const _,
+                frame_count,
+            )
+        })
+    }
 }
@@ -1,5 +1,5 @@
 use super::biquad_filtering::Biquad;
-use super::Filter;
+use super::{BiquadCoefficients, Filter, FrequencyResponse};
 use crate::base::{Error, Format};
 use crate::frames::{Frames, FramesMut};
 use miniaudio_sys as sys;
@@ -121,12 +121,23 @@ impl HighShelf2 {
         unsafe { &*(&self.0.bq as *const sys::ma_biquad as *const Biquad) }
     }
 
+    #[inline]
+    pub(crate) fn bq_mut(&mut self) -> &mut Biquad {
+        unsafe { &mut *(&mut self.0.bq as *mut sys::ma_biquad as *mut Biquad) }
+    }
+
     #[inline]
     pub fn latency(&self) -> u32 {
         unsafe { sys::ma_hishelf2_get_latency(&self.0 as *const _ as *mut _) }
     }
 }
 
+impl FrequencyResponse for HighShelf2 {
+    fn coefficients(&self) -> Vec<BiquadCoefficients> {
+        self.bq().coefficients()
+    }
+}
+
 impl Filter for HighShelf2 {
     #[inline]
     fn process_pcm_frames(&mut self, output: &mut FramesMut, input: &Frames) -> Result<(), Error> {
@@ -141,4 +152,19 @@ impl Filter for HighShelf2 {
             )
         })
     }
+
+    #[inline]
+    fn process_pcm_frames_in_place(&mut self, frames: &mut FramesMut) -> Result<(), Error> {
+        let frame_count = frames.frame_count() as u64;
+        let frames_ptr = frames.as_mut_ptr();
+
+        Error::from_c_result(unsafe {
+            sys::ma_hishelf2_process_pcm_frames(
+                &mut self.0 as *mut _,
+                frames_ptr as *mut _,
+                frames_ptr as *const _,
+                frame_count,
+            )
+        })
+    }
 }
@@ -1,6 +1,6 @@
-use super::biquad_filtering::Biquad;
-use super::Filter;
-use crate::base::{Error, Format};
+use super::biquad_filtering::{coefficient_to_f64, Biquad};
+use super::{BiquadCoefficients, Filter, FrequencyResponse};
+use crate::base::{Error, Format, MAX_FILTER_ORDER};
 use crate::frames::{Frames, FramesMut};
 use miniaudio_sys as sys;
 
@@ -75,7 +75,7 @@ impl LPF1Config {
 /// Configuration for a second order low-pass filter.
 #[repr(transparent)]
 #[derive(Clone)]
-pub struct LPF2Config(sys::ma_lpf1_config);
+pub struct LPF2Config(sys::ma_lpf2_config);
 
 impl LPF2Config {
     pub fn new(
@@ -169,6 +169,20 @@ impl LPF1 {
     }
 }
 
+impl FrequencyResponse for LPF1 {
+    fn coefficients(&self) -> Vec<BiquadCoefficients> {
+        // miniaudio applies this as `y = (1 - a)*x + a*y[n-1]`.
+        let a = coefficient_to_f64(Format::from_c(self.0.format), &self.0.a);
+        vec![BiquadCoefficients {
+            b0: 1.0 - a,
+            b1: 0.0,
+            b2: 0.0,
+            a1: -a,
+            a2: 0.0,
+        }]
+    }
+}
+
 impl Filter for LPF1 {
     #[inline]
     fn process_pcm_frames(&mut self, output: &mut FramesMut, input: &Frames) -> Result<(), Error> {
@@ -183,6 +197,21 @@ impl Filter for LPF1 {
             )
         })
     }
+
+    #[inline]
+    fn process_pcm_frames_in_place(&mut self, frames: &mut FramesMut) -> Result<(), Error> {
+        let frame_count = frames.frame_count() as u64;
+        let frames_ptr = frames.as_mut_ptr();
+
+        Error::from_c_result(unsafe {
+            sys::ma_lpf1_process_pcm_frames(
+                &mut self.0 as *mut _,
+                frames_ptr as *mut _,
+                frames_ptr as *const _,
+                frame_count,
+            )
+        })
+    }
 }
 
 /// Second order low-pass filter.
@@ -212,11 +241,21 @@ impl LPF2 {
         unsafe { (&self.0.bq as *const _ as *const Biquad).as_ref().unwrap() }
     }
 
+    pub(crate) fn biquad_mut(&mut self) -> &mut Biquad {
+        unsafe { &mut *(&mut self.0.bq as *mut sys::ma_biquad as *mut Biquad) }
+    }
+
     pub fn latency(&self) -> u32 {
         unsafe { sys::ma_lpf2_get_latency(&self.0 as *const _ as *mut _) }
     }
 }
 
+impl FrequencyResponse for LPF2 {
+    fn coefficients(&self) -> Vec<BiquadCoefficients> {
+        self.biquad().coefficients()
+    }
+}
+
 impl Filter for LPF2 {
     #[inline]
     fn process_pcm_frames(&mut self, output: &mut FramesMut, input: &Frames) -> Result<(), Error> {
@@ -231,6 +270,21 @@ impl Filter for LPF2 {
             )
         })
     }
+
+    #[inline]
+    fn process_pcm_frames_in_place(&mut self, frames: &mut FramesMut) -> Result<(), Error> {
+        let frame_count = frames.frame_count() as u64;
+        let frames_ptr = frames.as_mut_ptr();
+
+        Error::from_c_result(unsafe {
+            sys::ma_lpf2_process_pcm_frames(
+                &mut self.0 as *mut _,
+                frames_ptr as *mut _,
+                frames_ptr as *const _,
+                frame_count,
+            )
+        })
+    }
 }
 
 #[repr(transparent)]
@@ -329,6 +383,25 @@ impl LPF {
     }
 }
 
+impl FrequencyResponse for LPF {
+    fn coefficients(&self) -> Vec<BiquadCoefficients> {
+        // The first order section is applied before the second order sections.
+        let lpf1 = unsafe { &*(&self.0.lpf1 as *const [sys::ma_lpf1; 1] as *const [LPF1; 1]) };
+        let lpf2 = unsafe {
+            &*(&self.0.lpf2 as *const [sys::ma_lpf2; MAX_FILTER_ORDER / 2]
+                as *const [LPF2; MAX_FILTER_ORDER / 2])
+        };
+
+        let first_order = lpf1[..self.0.lpf1Count as usize]
+            .iter()
+            .flat_map(|lpf1| lpf1.coefficients());
+        let second_order = lpf2[..self.0.lpf2Count as usize]
+            .iter()
+            .flat_map(|lpf2| lpf2.coefficients());
+        first_order.chain(second_order).collect()
+    }
+}
+
 impl Filter for LPF {
     #[inline]
     fn process_pcm_frames(&mut self, output: &mut FramesMut, input: &Frames) -> Result<(), Error> {
@@ -343,4 +416,19 @@ impl Filter for LPF {
             )
         })
     }
+
+    #[inline]
+    fn process_pcm_frames_in_place(&mut self, frames: &mut FramesMut) -> Result<(), Error> {
+        let frame_count = frames.frame_count() as u64;
+        let frames_ptr = frames.as_mut_ptr();
+
+        Error::from_c_result(unsafe {
+            sys::ma_lpf_process_pcm_frames(
+                &mut self.0 as *mut _,
+                frames_ptr as *mut _,
+                frames_ptr as *const _,
+                frame_count,
+            )
+        })
+    }
 }
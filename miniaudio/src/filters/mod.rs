@@ -1,17 +1,107 @@
 pub mod band_pass_filtering;
 pub mod biquad_filtering;
+pub mod filter_chain;
 pub mod high_pass_filtering;
 pub mod high_shelf_filter;
 pub mod low_pass_filtering;
 pub mod low_shelf_filter;
 pub mod notching_filter;
+pub mod parametric_eq;
 pub mod peaking_eq_filter;
 
 use crate::base::Error;
-use crate::frames::{Frames, FramesMut};
+use crate::frames::{process_in_place_chunked, Frames, FramesMut};
 
 pub trait Filter {
     fn process_pcm_frames(&mut self, output: &mut FramesMut, input: &Frames) -> Result<(), Error>;
+
+    /// Filters `frames`, replacing them with the result. This does not allocate, except in a
+    /// `FilterChain` that has to grow its scratch buffer.
+    ///
+    /// The default implementation copies the frames onto the stack in chunks that are then used as
+    /// the input to `process_pcm_frames`. All of the filters in this module override this and
+    /// filter the frames directly.
+    fn process_pcm_frames_in_place(&mut self, frames: &mut FramesMut) -> Result<(), Error> {
+        process_in_place_chunked(frames, |output, input| {
+            self.process_pcm_frames(output, input)
+        })
+    }
+}
+
+/// The normalized coefficients of a single second order section, where `a0` is always 1:
+///
+/// `H(z) = (b0 + b1*z^-1 + b2*z^-2) / (1 + a1*z^-1 + a2*z^-2)`
+///
+/// First order filters are described with `b2` and `a2` set to 0.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BiquadCoefficients {
+    pub b0: f64,
+    pub b1: f64,
+    pub b2: f64,
+    pub a1: f64,
+    pub a2: f64,
+}
+
+impl BiquadCoefficients {
+    /// Evaluates this section at `frequency` (in Hz), returning the linear magnitude and the phase
+    /// in radians.
+    pub fn response(&self, frequency: f64, sample_rate: u32) -> (f64, f64) {
+        let w = 2.0 * std::f64::consts::PI * frequency / sample_rate as f64;
+        let (sin1, cos1) = w.sin_cos();
+        let (sin2, cos2) = (2.0 * w).sin_cos();
+
+        let num_re = self.b0 + self.b1 * cos1 + self.b2 * cos2;
+        let num_im = -(self.b1 * sin1 + self.b2 * sin2);
+        let den_re = 1.0 + self.a1 * cos1 + self.a2 * cos2;
+        let den_im = -(self.a1 * sin1 + self.a2 * sin2);
+
+        let magnitude = num_re.hypot(num_im) / den_re.hypot(den_im);
+        let phase = num_im.atan2(num_re) - den_im.atan2(den_re);
+        (magnitude, phase)
+    }
+}
+
+/// Evaluates the response of a filter without having to run any frames through it. This is
+/// implemented by every filter in this module and can be used for things like drawing EQ curves.
+///
+/// The coefficients are read back from the filter, so for `Format::S16` filters this reflects the
+/// precision loss of their fixed-point coefficients.
+pub trait FrequencyResponse {
+    /// The second order sections making up this filter in the order they are applied. A filter
+    /// that does not apply any filtering (e.g. an order of 0) returns no sections.
+    fn coefficients(&self) -> Vec<BiquadCoefficients>;
+
+    /// Returns the linear magnitude and the phase in radians (wrapped to `[-pi, pi]`) of this
+    /// filter at `frequency` (in Hz).
+    fn response(&self, frequency: f64, sample_rate: u32) -> (f64, f64) {
+        let (magnitude, phase) =
+            self.coefficients()
+                .iter()
+                .fold((1.0, 0.0), |(magnitude, phase), section| {
+                    let (m, p) = section.response(frequency, sample_rate);
+                    (magnitude * m, phase + p)
+                });
+        (magnitude, wrap_phase(phase))
+    }
+
+    /// The linear magnitude of this filter at `frequency` (in Hz).
+    fn magnitude(&self, frequency: f64, sample_rate: u32) -> f64 {
+        self.response(frequency, sample_rate).0
+    }
+
+    /// The magnitude of this filter at `frequency` (in Hz) in decibels.
+    fn magnitude_db(&self, frequency: f64, sample_rate: u32) -> f64 {
+        20.0 * self.magnitude(frequency, sample_rate).log10()
+    }
+
+    /// The phase of this filter at `frequency` (in Hz) in radians, wrapped to `[-pi, pi]`.
+    fn phase(&self, frequency: f64, sample_rate: u32) -> f64 {
+        self.response(frequency, sample_rate).1
+    }
+}
+
+fn wrap_phase(phase: f64) -> f64 {
+    phase.sin().atan2(phase.cos())
 }
 
 fn ensure_same_format(output: &mut FramesMut, input: &Frames) -> Result<(), Error> {
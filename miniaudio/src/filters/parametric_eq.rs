@@ -0,0 +1,373 @@
+use super::biquad_filtering::Biquad;
+use super::high_pass_filtering::{HPF2Config, HPF2};
+use super::high_shelf_filter::{HighShelf2, HighShelf2Config};
+use super::low_pass_filtering::{LPF2Config, LPF2};
+use super::low_shelf_filter::{LowShelf2, LowShelf2Config};
+use super::notching_filter::{Notch2, Notch2Config};
+use super::peaking_eq_filter::{Peak2, Peak2Config};
+use super::{BiquadCoefficients, Filter, FrequencyResponse};
+use crate::base::{Error, Format};
+use crate::frames::{Frames, FramesMut};
+
+/// The kind of filter used by a band of a `ParametricEq`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EqBandType {
+    /// Boosts or cuts everything below the band's frequency. The band's Q is used as the shelf
+    /// slope.
+    LowShelf,
+    /// Boosts or cuts everything above the band's frequency. The band's Q is used as the shelf
+    /// slope.
+    HighShelf,
+    /// Boosts or cuts frequencies around the band's frequency.
+    Peak,
+    /// Removes frequencies around the band's frequency. The band's gain is ignored.
+    Notch,
+    /// Removes frequencies above the band's frequency. The band's gain is ignored.
+    LowPass,
+    /// Removes frequencies below the band's frequency. The band's gain is ignored.
+    HighPass,
+}
+
+/// The parameters of a single band of a `ParametricEq`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EqBand {
+    pub band_type: EqBandType,
+    pub frequency: f64,
+    pub gain_db: f64,
+    pub q: f64,
+
+    /// A bypassed band is skipped while processing, so its filter history is not updated. When the
+    /// band is enabled again its history is cleared instead of resuming from stale state.
+    /// Toggling this switches the band on or off instantly, without any crossfade.
+    pub bypass: bool,
+}
+
+impl EqBand {
+    #[inline]
+    pub fn new(band_type: EqBandType, frequency: f64, gain_db: f64, q: f64) -> EqBand {
+        EqBand {
+            band_type,
+            frequency,
+            gain_db,
+            q,
+            bypass: false,
+        }
+    }
+}
+
+#[derive(Clone)]
+enum EqBandFilter {
+    LowShelf(LowShelf2),
+    HighShelf(HighShelf2),
+    Peak(Peak2),
+    Notch(Notch2),
+    LowPass(LPF2),
+    HighPass(HPF2),
+}
+
+impl EqBandFilter {
+    fn new(format: Format, channels: u32, sample_rate: u32, band: &EqBand) -> Result<Self, Error> {
+        Ok(match band.band_type {
+            EqBandType::LowShelf => EqBandFilter::LowShelf(LowShelf2::new(
+                &Self::low_shelf_config(format, channels, sample_rate, band),
+            )?),
+            EqBandType::HighShelf => EqBandFilter::HighShelf(HighShelf2::new(
+                &Self::high_shelf_config(format, channels, sample_rate, band),
+            )?),
+            EqBandType::Peak => EqBandFilter::Peak(Peak2::new(&Self::peak_config(
+                format,
+                channels,
+                sample_rate,
+                band,
+            ))?),
+            EqBandType::Notch => EqBandFilter::Notch(Notch2::new(&Self::notch_config(
+                format,
+                channels,
+                sample_rate,
+                band,
+            ))?),
+            EqBandType::LowPass => EqBandFilter::LowPass(LPF2::new(&Self::lpf2_config(
+                format,
+                channels,
+                sample_rate,
+                band,
+            ))?),
+            EqBandType::HighPass => EqBandFilter::HighPass(HPF2::new(&Self::hpf2_config(
+                format,
+                channels,
+                sample_rate,
+                band,
+            ))?),
+        })
+    }
+
+    /// Updates the filter in place, which keeps its history so that there are no discontinuities in
+    /// the output. Returns false if the band type changed and the filter has to be rebuilt instead.
+    fn reinit(
+        &mut self,
+        format: Format,
+        channels: u32,
+        sample_rate: u32,
+        band: &EqBand,
+    ) -> Result<bool, Error> {
+        match (self, band.band_type) {
+            (EqBandFilter::LowShelf(f), EqBandType::LowShelf) => {
+                f.reinit(&Self::low_shelf_config(format, channels, sample_rate, band))?
+            }
+            (EqBandFilter::HighShelf(f), EqBandType::HighShelf) => f.reinit(
+                &Self::high_shelf_config(format, channels, sample_rate, band),
+            )?,
+            (EqBandFilter::Peak(f), EqBandType::Peak) => {
+                f.reinit(&Self::peak_config(format, channels, sample_rate, band))?
+            }
+            (EqBandFilter::Notch(f), EqBandType::Notch) => {
+                f.reinit(&Self::notch_config(format, channels, sample_rate, band))?
+            }
+            (EqBandFilter::LowPass(f), EqBandType::LowPass) => {
+                f.reinit(&Self::lpf2_config(format, channels, sample_rate, band))?
+            }
+            (EqBandFilter::HighPass(f), EqBandType::HighPass) => {
+                f.reinit(&Self::hpf2_config(format, channels, sample_rate, band))?
+            }
+            _ => return Ok(false),
+        }
+
+        Ok(true)
+    }
+
+    fn biquad(&self) -> &Biquad {
+        match self {
+            EqBandFilter::LowShelf(f) => f.bq(),
+            EqBandFilter::HighShelf(f) => f.bq(),
+            EqBandFilter::Peak(f) => f.bq(),
+            EqBandFilter::Notch(f) => f.bq(),
+            EqBandFilter::LowPass(f) => f.biquad(),
+            EqBandFilter::HighPass(f) => f.biquad(),
+        }
+    }
+
+    fn biquad_mut(&mut self) -> &mut Biquad {
+        match self {
+            EqBandFilter::LowShelf(f) => f.bq_mut(),
+            EqBandFilter::HighShelf(f) => f.bq_mut(),
+            EqBandFilter::Peak(f) => f.bq_mut(),
+            EqBandFilter::Notch(f) => f.bq_mut(),
+            EqBandFilter::LowPass(f) => f.biquad_mut(),
+            EqBandFilter::HighPass(f) => f.biquad_mut(),
+        }
+    }
+
+    fn low_shelf_config(f: Format, c: u32, sr: u32, band: &EqBand) -> LowShelf2Config {
+        LowShelf2Config::new(f, c, sr, band.gain_db, band.q, band.frequency)
+    }
+
+    fn high_shelf_config(f: Format, c: u32, sr: u32, band: &EqBand) -> HighShelf2Config {
+        HighShelf2Config::new(f, c, sr, band.gain_db, band.q, band.frequency)
+    }
+
+    fn peak_config(f: Format, c: u32, sr: u32, band: &EqBand) -> Peak2Config {
+        Peak2Config::new(f, c, sr, band.gain_db, band.q, band.frequency)
+    }
+
+    fn notch_config(f: Format, c: u32, sr: u32, band: &EqBand) -> Notch2Config {
+        Notch2Config::new(f, c, sr, band.q, band.frequency)
+    }
+
+    fn lpf2_config(f: Format, c: u32, sr: u32, band: &EqBand) -> LPF2Config {
+        LPF2Config::new(f, c, sr, band.frequency, band.q)
+    }
+
+    fn hpf2_config(f: Format, c: u32, sr: u32, band: &EqBand) -> HPF2Config {
+        HPF2Config::new(f, c, sr, band.frequency, band.q)
+    }
+}
+
+#[derive(Clone)]
+struct EqBandState {
+    params: EqBand,
+    filter: EqBandFilter,
+}
+
+/// A parametric equalizer made up of any number of second order bands that are applied one after
+/// the other. Only `Format::F32` and `Format::S16` are supported.
+#[derive(Clone)]
+pub struct ParametricEq {
+    format: Format,
+    channels: u32,
+    sample_rate: u32,
+    bands: Vec<EqBandState>,
+}
+
+impl ParametricEq {
+    pub fn new(
+        format: Format,
+        channels: u32,
+        sample_rate: u32,
+        bands: &[EqBand],
+    ) -> Result<ParametricEq, Error> {
+        let mut eq = ParametricEq {
+            format,
+            channels,
+            sample_rate,
+            bands: Vec::with_capacity(bands.len()),
+        };
+
+        for band in bands {
+            eq.add_band(*band)?;
+        }
+
+        Ok(eq)
+    }
+
+    /// Adds a band after all of the existing bands, returning its index.
+    pub fn add_band(&mut self, band: EqBand) -> Result<usize, Error> {
+        let filter = EqBandFilter::new(self.format, self.channels, self.sample_rate, &band)?;
+        self.bands.push(EqBandState {
+            params: band,
+            filter,
+        });
+        Ok(self.bands.len() - 1)
+    }
+
+    /// Removes the band at `index`, returning its parameters. This will panic if `index` is out of
+    /// bounds.
+    pub fn remove_band(&mut self, index: usize) -> EqBand {
+        self.bands.remove(index).params
+    }
+
+    #[inline]
+    pub fn band_count(&self) -> usize {
+        self.bands.len()
+    }
+
+    #[inline]
+    pub fn band(&self, index: usize) -> Option<&EqBand> {
+        self.bands.get(index).map(|state| &state.params)
+    }
+
+    pub fn bands(&self) -> impl '_ + Iterator<Item = &EqBand> {
+        self.bands.iter().map(|state| &state.params)
+    }
+
+    /// Changes the parameters of the band at `index`. If the band type is unchanged, the band's
+    /// filter is updated in place and keeps its history so that there is no audible glitch. If
+    /// the band type changed or the band is being enabled again (see `EqBand::bypass`), the band's
+    /// filter is rebuilt.
+    ///
+    /// This will fail with `InvalidArgs` if `index` is out of bounds.
+    pub fn set_band(&mut self, index: usize, band: EqBand) -> Result<(), Error> {
+        let (format, channels, sample_rate) = (self.format, self.channels, self.sample_rate);
+        let state = self.bands.get_mut(index).ok_or(Error::InvalidArgs)?;
+
+        let enabling = state.params.bypass && !band.bypass;
+        if enabling || !state.filter.reinit(format, channels, sample_rate, &band)? {
+            state.filter = EqBandFilter::new(format, channels, sample_rate, &band)?;
+        }
+
+        state.params = band;
+        Ok(())
+    }
+
+    /// Enables or disables the band at `index` without changing any of its other parameters.
+    /// Enabling a band clears its filter history, see `EqBand::bypass`.
+    ///
+    /// This will fail with `InvalidArgs` if `index` is out of bounds.
+    pub fn set_bypass(&mut self, index: usize, bypass: bool) -> Result<(), Error> {
+        let band = *self.band(index).ok_or(Error::InvalidArgs)?;
+        self.set_band(index, EqBand { bypass, ..band })
+    }
+
+    /// The combined linear magnitude of all of the bands that are not bypassed at `frequency`,
+    /// using the sample rate of this equalizer.
+    pub fn magnitude_response(&self, frequency: f64) -> f64 {
+        self.magnitude(frequency, self.sample_rate)
+    }
+
+    /// The combined magnitude of all of the bands that are not bypassed at `frequency` in
+    /// decibels, using the sample rate of this equalizer.
+    pub fn magnitude_response_db(&self, frequency: f64) -> f64 {
+        self.magnitude_db(frequency, self.sample_rate)
+    }
+
+    /// The combined latency of all of the bands that are not bypassed.
+    pub fn latency(&self) -> u32 {
+        self.active_bands()
+            .map(|state| state.filter.biquad().latency())
+            .sum()
+    }
+
+    #[inline]
+    pub fn format(&self) -> Format {
+        self.format
+    }
+
+    #[inline]
+    pub fn channels(&self) -> u32 {
+        self.channels
+    }
+
+    #[inline]
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn active_bands(&self) -> impl '_ + Iterator<Item = &EqBandState> {
+        self.bands.iter().filter(|state| !state.params.bypass)
+    }
+}
+
+impl FrequencyResponse for ParametricEq {
+    /// The sections of all of the bands that are not bypassed.
+    fn coefficients(&self) -> Vec<BiquadCoefficients> {
+        self.active_bands()
+            .flat_map(|state| state.filter.biquad().coefficients())
+            .collect()
+    }
+}
+
+impl Filter for ParametricEq {
+    fn process_pcm_frames(&mut self, output: &mut FramesMut, input: &Frames) -> Result<(), Error> {
+        super::ensure_frames_compat(output, input)?;
+
+        let frame_count = output.frame_count() as u64;
+        let output_ptr = output.as_mut_ptr();
+        let mut input_ptr = input.as_ptr();
+
+        for state in self.bands.iter_mut().filter(|state| !state.params.bypass) {
+            // The first band reads from the input and every band after that processes the output
+            // in place.
+            unsafe {
+                state.filter.biquad_mut().process_pcm_frames_raw(
+                    output_ptr,
+                    input_ptr,
+                    frame_count,
+                )?
+            };
+            input_ptr = output_ptr;
+        }
+
+        // With no active bands the input is passed through untouched.
+        if input_ptr != output_ptr {
+            output.as_bytes_mut().copy_from_slice(input.as_bytes());
+        }
+
+        Ok(())
+    }
+
+    fn process_pcm_frames_in_place(&mut self, frames: &mut FramesMut) -> Result<(), Error> {
+        let frame_count = frames.frame_count() as u64;
+        let frames_ptr = frames.as_mut_ptr();
+
+        for state in self.bands.iter_mut().filter(|state| !state.params.bypass) {
+            unsafe {
+                state.filter.biquad_mut().process_pcm_frames_raw(
+                    frames_ptr,
+                    frames_ptr,
+                    frame_count,
+                )?
+            };
+        }
+
+        Ok(())
+    }
+}
@@ -1,5 +1,7 @@
 use crate::base::*;
 use crate::conversion::conversion_fn_for;
+use crate::volume::clip_samples_f32;
+use miniaudio_sys as sys;
 
 pub struct Frames<'s> {
     data: &'s [u8],
@@ -74,6 +76,21 @@ impl<'s> Frames<'s> {
         }
     }
 
+    /// Returns a view of these frames with the sample type `S`, or `None` if the format of these
+    /// frames is not the format of `S` or if they don't hold a whole number of frames (which
+    /// includes having 0 channels).
+    #[inline]
+    pub fn typed<S: Sample>(&self) -> Option<TypedFrames<'s, S>> {
+        if self.format != S::format() || !is_whole_frames::<S>(self.data.len(), self.channels) {
+            return None;
+        }
+
+        let samples = unsafe {
+            std::slice::from_raw_parts(self.data.as_ptr().cast::<S>(), self.sample_count())
+        };
+        Some(TypedFrames::new(samples, self.channels))
+    }
+
     #[inline]
     pub fn byte_count(&self) -> usize {
         self.data.len()
@@ -204,6 +221,74 @@ impl<'s> FramesMut<'s> {
         }
     }
 
+    /// Multiplies every sample by `factor`, where 1 leaves the frames unchanged and 0 silences
+    /// them. Use `gain_db_to_factor` to apply a gain in decibels.
+    #[inline]
+    pub fn apply_volume_factor(&mut self, factor: f32) {
+        unsafe {
+            sys::ma_apply_volume_factor_pcm_frames(
+                self.as_mut_ptr() as *mut _,
+                self.frame_count() as u64,
+                self.format as _,
+                self.channels,
+                factor,
+            )
+        };
+    }
+
+    /// Copies `src` into these frames while multiplying every sample by `factor`. Use
+    /// `gain_db_to_factor` to apply a gain in decibels.
+    #[inline]
+    pub fn copy_with_volume_factor(&mut self, src: &Frames, factor: f32) {
+        assert!(
+            self.format == src.format() && self.channels == src.channels(),
+            "copy with different formats or channel counts (src: {:?}/{}, dest: {:?}/{})",
+            src.format(),
+            src.channels(),
+            self.format,
+            self.channels
+        );
+        assert!(
+            self.frame_count() == src.frame_count(),
+            "copy with different frame counts (src: {}, dest: {})",
+            src.frame_count(),
+            self.frame_count()
+        );
+
+        unsafe {
+            sys::ma_copy_and_apply_volume_factor_pcm_frames(
+                self.as_mut_ptr() as *mut _,
+                src.as_ptr() as *const _,
+                self.frame_count() as u64,
+                self.format as _,
+                self.channels,
+                factor,
+            )
+        };
+    }
+
+    /// Clamps every sample to the range `[-1, 1]`. This will panic if the format of these frames
+    /// is not `Format::F32`.
+    #[inline]
+    pub fn clip(&mut self) {
+        clip_samples_f32(self.as_samples_mut());
+    }
+
+    /// Returns a mutable view of these frames with the sample type `S`, or `None` if the format of
+    /// these frames is not the format of `S` or if they don't hold a whole number of frames (which
+    /// includes having 0 channels).
+    #[inline]
+    pub fn typed_mut<S: Sample>(&mut self) -> Option<TypedFramesMut<'_, S>> {
+        if self.format != S::format() || !is_whole_frames::<S>(self.data.len(), self.channels) {
+            return None;
+        }
+
+        let samples = unsafe {
+            std::slice::from_raw_parts_mut(self.data.as_mut_ptr().cast::<S>(), self.sample_count())
+        };
+        Some(TypedFramesMut::new(samples, self.channels))
+    }
+
     #[inline]
     pub fn as_bytes(&self) -> &[u8] {
         self.data
@@ -242,6 +327,333 @@ impl<'s> FramesMut<'s> {
     }
 }
 
+/// The size in bytes of the stack buffer used by `process_in_place_chunked`.
+const IN_PLACE_CHUNK_SIZE: usize = 4096;
+
+/// Runs `process` over `frames` in place for processors that can't have their output and input
+/// point to the same frames. Chunks of `frames` are copied into a buffer on the stack that is then
+/// used as the input for that chunk, so nothing is allocated.
+///
+/// This will fail with `InvalidArgs` if a single frame doesn't fit in the chunk, which can only
+/// happen with a very large channel count.
+pub(crate) fn process_in_place_chunked<F>(
+    frames: &mut FramesMut,
+    mut process: F,
+) -> Result<(), Error>
+where
+    F: FnMut(&mut FramesMut, &Frames) -> Result<(), Error>,
+{
+    // NOTE: This is stored as `u32` so that the chunk is aligned for every format.
+    let mut chunk = [0u32; IN_PLACE_CHUNK_SIZE / std::mem::size_of::<u32>()];
+    let chunk = unsafe {
+        std::slice::from_raw_parts_mut(chunk.as_mut_ptr() as *mut u8, IN_PLACE_CHUNK_SIZE)
+    };
+
+    let (format, channels) = (frames.format(), frames.channels());
+    let bytes_per_frame = format.size_in_bytes() * channels as usize;
+    if bytes_per_frame == 0 {
+        return Ok(());
+    }
+    if bytes_per_frame > IN_PLACE_CHUNK_SIZE {
+        ma_debug_panic!(
+            "a single frame ({} bytes) does not fit in the in-place chunk ({} bytes)",
+            bytes_per_frame,
+            IN_PLACE_CHUNK_SIZE
+        );
+        return Err(Error::InvalidArgs);
+    }
+    let chunk_len = (IN_PLACE_CHUNK_SIZE / bytes_per_frame) * bytes_per_frame;
+
+    for frames_chunk in frames.as_bytes_mut().chunks_mut(chunk_len) {
+        let input = &mut chunk[..frames_chunk.len()];
+        input.copy_from_slice(frames_chunk);
+        process(
+            &mut FramesMut::wrap::<u8>(frames_chunk, format, channels),
+            &Frames::wrap::<u8>(input, format, channels),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Borrowed frames where the format is derived from the sample type `S`, so it can't disagree with
+/// the actual samples. These are cheap to convert to and from `Frames`.
+#[derive(Clone, Copy)]
+pub struct TypedFrames<'s, S: Sample> {
+    samples: &'s [S],
+    channels: u32,
+}
+
+impl<'s, S: Sample> TypedFrames<'s, S> {
+    /// This will panic if the number of samples is not a multiple of `channels`.
+    #[inline]
+    pub fn new(samples: &'s [S], channels: u32) -> TypedFrames<'s, S> {
+        assert_whole_frames(samples.len(), channels);
+        TypedFrames { samples, channels }
+    }
+
+    #[inline]
+    pub fn as_samples(&self) -> &'s [S] {
+        self.samples
+    }
+
+    #[inline]
+    pub fn frames(&self) -> std::slice::ChunksExact<'s, S> {
+        self.samples.chunks_exact(self.channels as usize)
+    }
+
+    #[inline]
+    pub fn as_frames(&self) -> Frames<'s> {
+        Frames::wrap(self.samples, S::format(), self.channels)
+    }
+
+    /// Returns the number of frames contained.
+    #[inline]
+    pub fn frame_count(&self) -> usize {
+        self.samples.len() / self.channels as usize
+    }
+
+    /// Returns the number of samples contained.
+    #[inline]
+    pub fn sample_count(&self) -> usize {
+        self.samples.len()
+    }
+
+    #[inline]
+    pub fn format(&self) -> Format {
+        S::format()
+    }
+
+    #[inline]
+    pub fn channels(&self) -> u32 {
+        self.channels
+    }
+}
+
+impl<'s, S: Sample> From<TypedFrames<'s, S>> for Frames<'s> {
+    fn from(frames: TypedFrames<'s, S>) -> Frames<'s> {
+        frames.as_frames()
+    }
+}
+
+/// Mutably borrowed frames where the format is derived from the sample type `S`, so it can't
+/// disagree with the actual samples. These are cheap to convert to and from `FramesMut`.
+pub struct TypedFramesMut<'s, S: Sample> {
+    samples: &'s mut [S],
+    channels: u32,
+}
+
+impl<'s, S: Sample> TypedFramesMut<'s, S> {
+    /// This will panic if the number of samples is not a multiple of `channels`.
+    #[inline]
+    pub fn new(samples: &'s mut [S], channels: u32) -> TypedFramesMut<'s, S> {
+        assert_whole_frames(samples.len(), channels);
+        TypedFramesMut { samples, channels }
+    }
+
+    #[inline]
+    pub fn as_samples(&self) -> &[S] {
+        self.samples
+    }
+
+    #[inline]
+    pub fn as_samples_mut(&mut self) -> &mut [S] {
+        self.samples
+    }
+
+    #[inline]
+    pub fn frames(&self) -> std::slice::ChunksExact<'_, S> {
+        self.samples.chunks_exact(self.channels as usize)
+    }
+
+    #[inline]
+    pub fn frames_mut(&mut self) -> std::slice::ChunksExactMut<'_, S> {
+        self.samples.chunks_exact_mut(self.channels as usize)
+    }
+
+    #[inline]
+    pub fn as_frames(&self) -> Frames<'_> {
+        Frames::wrap(self.samples, S::format(), self.channels)
+    }
+
+    #[inline]
+    pub fn as_frames_mut(&mut self) -> FramesMut<'_> {
+        FramesMut::wrap(self.samples, S::format(), self.channels)
+    }
+
+    /// Returns the number of frames contained.
+    #[inline]
+    pub fn frame_count(&self) -> usize {
+        self.samples.len() / self.channels as usize
+    }
+
+    /// Returns the number of samples contained.
+    #[inline]
+    pub fn sample_count(&self) -> usize {
+        self.samples.len()
+    }
+
+    #[inline]
+    pub fn format(&self) -> Format {
+        S::format()
+    }
+
+    #[inline]
+    pub fn channels(&self) -> u32 {
+        self.channels
+    }
+}
+
+impl<'s, S: Sample> From<TypedFramesMut<'s, S>> for FramesMut<'s> {
+    fn from(frames: TypedFramesMut<'s, S>) -> FramesMut<'s> {
+        FramesMut::wrap(frames.samples, S::format(), frames.channels)
+    }
+}
+
+/// An owned buffer of interleaved frames where the format is derived from the sample type `S`.
+#[derive(Clone)]
+pub struct FrameBuffer<S: Sample> {
+    samples: Vec<S>,
+    channels: u32,
+}
+
+impl<S: Sample + Copy + Default> FrameBuffer<S> {
+    /// Creates a buffer containing `frame_count` frames of silence.
+    pub fn silent(channels: u32, frame_count: usize) -> FrameBuffer<S> {
+        assert_whole_frames(0, channels);
+        let mut buffer = FrameBuffer {
+            samples: vec![S::default(); frame_count * channels as usize],
+            channels,
+        };
+        buffer.silence();
+        buffer
+    }
+}
+
+impl<S: Sample> FrameBuffer<S> {
+    /// Creates a buffer from interleaved samples. This will panic if the number of samples is not a
+    /// multiple of `channels`.
+    pub fn from_samples(samples: Vec<S>, channels: u32) -> FrameBuffer<S> {
+        assert_whole_frames(samples.len(), channels);
+        FrameBuffer { samples, channels }
+    }
+
+    /// Copies `frames` into a new buffer, or returns `None` if the format of `frames` is not the
+    /// format of `S`.
+    pub fn from_frames(frames: &Frames) -> Option<FrameBuffer<S>>
+    where
+        S: Clone,
+    {
+        let typed = frames.typed::<S>()?;
+        Some(FrameBuffer {
+            samples: typed.as_samples().to_vec(),
+            channels: typed.channels(),
+        })
+    }
+
+    /// Fills every frame in this buffer with silence.
+    pub fn silence(&mut self) {
+        let frame_count = self.frame_count() as u64;
+        unsafe {
+            sys::ma_silence_pcm_frames(
+                self.samples.as_mut_ptr().cast(),
+                frame_count,
+                S::format() as _,
+                self.channels,
+            )
+        };
+    }
+
+    #[inline]
+    pub fn as_samples(&self) -> &[S] {
+        &self.samples
+    }
+
+    #[inline]
+    pub fn as_samples_mut(&mut self) -> &mut [S] {
+        &mut self.samples
+    }
+
+    #[inline]
+    pub fn into_samples(self) -> Vec<S> {
+        self.samples
+    }
+
+    #[inline]
+    pub fn frames(&self) -> std::slice::ChunksExact<'_, S> {
+        self.samples.chunks_exact(self.channels as usize)
+    }
+
+    #[inline]
+    pub fn frames_mut(&mut self) -> std::slice::ChunksExactMut<'_, S> {
+        self.samples.chunks_exact_mut(self.channels as usize)
+    }
+
+    #[inline]
+    pub fn typed(&self) -> TypedFrames<'_, S> {
+        TypedFrames {
+            samples: &self.samples,
+            channels: self.channels,
+        }
+    }
+
+    #[inline]
+    pub fn typed_mut(&mut self) -> TypedFramesMut<'_, S> {
+        TypedFramesMut {
+            samples: &mut self.samples,
+            channels: self.channels,
+        }
+    }
+
+    #[inline]
+    pub fn as_frames(&self) -> Frames<'_> {
+        Frames::wrap(&self.samples, S::format(), self.channels)
+    }
+
+    #[inline]
+    pub fn as_frames_mut(&mut self) -> FramesMut<'_> {
+        FramesMut::wrap(&mut self.samples, S::format(), self.channels)
+    }
+
+    /// Returns the number of frames contained.
+    #[inline]
+    pub fn frame_count(&self) -> usize {
+        self.samples.len() / self.channels as usize
+    }
+
+    /// Returns the number of samples contained.
+    #[inline]
+    pub fn sample_count(&self) -> usize {
+        self.samples.len()
+    }
+
+    #[inline]
+    pub fn format(&self) -> Format {
+        S::format()
+    }
+
+    #[inline]
+    pub fn channels(&self) -> u32 {
+        self.channels
+    }
+}
+
+/// Returns true if `byte_count` bytes of samples of type `S` make up a whole number of frames with
+/// `channels` channels.
+fn is_whole_frames<S: Sample>(byte_count: usize, channels: u32) -> bool {
+    let bytes_per_frame = std::mem::size_of::<S>() * channels as usize;
+    bytes_per_frame > 0 && byte_count.is_multiple_of(bytes_per_frame)
+}
+
+fn assert_whole_frames(sample_count: usize, channels: u32) {
+    assert!(
+        channels > 0 && sample_count.is_multiple_of(channels as usize),
+        "sample count is not a multiple of the channel count (samples: {}, channels: {})",
+        sample_count,
+        channels
+    );
+}
+
 pub struct FramesIter<'s, S: Sample> {
     samples: &'s [S],
     channels: u32,
@@ -41,27 +41,45 @@ macro_rules! impl_from_c {
     };
 }
 
+mod audio_buffer;
 mod base;
 mod channel_conv;
+mod channels;
 mod conversion;
 mod data_conv;
+mod data_source;
 mod decoder;
 mod device_io;
+mod encoder;
 mod filters;
 mod frames;
 mod generation;
 mod lock;
+mod planar_frames;
+mod processor;
 mod resampling;
+mod resilient_device;
 mod ring_buffers;
+mod vfs;
+mod volume;
 
+pub use audio_buffer::*;
 pub use base::*;
 pub use channel_conv::*;
+pub use channels::*;
 pub use conversion::*;
 pub use data_conv::*;
+pub use data_source::*;
 pub use decoder::*;
 pub use device_io::*;
+pub use encoder::*;
 pub use filters::*;
 pub use frames::*;
 pub use generation::*;
+pub use planar_frames::*;
+pub use processor::*;
 pub use resampling::*;
+pub use resilient_device::*;
 pub use ring_buffers::*;
+pub use vfs::*;
+pub use volume::*;
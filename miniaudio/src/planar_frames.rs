@@ -0,0 +1,208 @@
+use crate::base::{Error, Format, MAX_CHANNELS};
+use crate::frames::{FrameBuffer, Frames, FramesMut, Sample};
+use miniaudio_sys as sys;
+
+/// Deinterleaved frames where every channel is stored in its own slice, as used by a lot of DSP
+/// code and plugin hosts. The format is derived from the sample type `S`.
+#[derive(Clone, Copy)]
+pub struct PlanarFrames<'a, S: Sample> {
+    channels: &'a [&'a [S]],
+    frame_count: usize,
+}
+
+impl<'a, S: Sample> PlanarFrames<'a, S> {
+    /// This will panic if there are no channels, more than `MAX_CHANNELS` channels or if the
+    /// channels are not all the same length.
+    pub fn new(channels: &'a [&'a [S]]) -> PlanarFrames<'a, S> {
+        let frame_count = planar_frame_count(channels.iter().map(|channel| channel.len()));
+        PlanarFrames {
+            channels,
+            frame_count,
+        }
+    }
+
+    /// Returns the samples of the channel at `index`. This will panic if `index` is out of bounds.
+    #[inline]
+    pub fn channel(&self, index: usize) -> &'a [S] {
+        self.channels[index]
+    }
+
+    #[inline]
+    pub fn channel_slices(&self) -> &'a [&'a [S]] {
+        self.channels
+    }
+
+    /// Interleaves these frames into `dest`, which must have the same format, channel count and
+    /// frame count as these frames.
+    pub fn interleave_into(&self, dest: &mut FramesMut) -> Result<(), Error> {
+        ensure_planar_compat(
+            (dest.format(), dest.channels(), dest.frame_count()),
+            (self.format(), self.channels(), self.frame_count()),
+        )?;
+
+        let mut channel_ptrs = [std::ptr::null(); MAX_CHANNELS];
+        for (ptr, channel) in channel_ptrs.iter_mut().zip(self.channels) {
+            *ptr = channel.as_ptr() as *const _;
+        }
+
+        unsafe {
+            sys::ma_interleave_pcm_frames(
+                S::format() as _,
+                self.channels(),
+                self.frame_count as u64,
+                channel_ptrs.as_mut_ptr(),
+                dest.as_mut_ptr() as *mut _,
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Interleaves these frames into a new buffer.
+    pub fn to_interleaved(&self) -> FrameBuffer<S>
+    where
+        S: Copy + Default,
+    {
+        let mut buffer = FrameBuffer::silent(self.channels(), self.frame_count);
+        self.interleave_into(&mut buffer.as_frames_mut())
+            .expect("interleaving into a buffer of the same shape failed");
+        buffer
+    }
+
+    /// Returns the number of frames contained, which is the length of each channel.
+    #[inline]
+    pub fn frame_count(&self) -> usize {
+        self.frame_count
+    }
+
+    #[inline]
+    pub fn format(&self) -> Format {
+        S::format()
+    }
+
+    #[inline]
+    pub fn channels(&self) -> u32 {
+        self.channels.len() as u32
+    }
+}
+
+/// Mutable deinterleaved frames where every channel is stored in its own slice. The format is
+/// derived from the sample type `S`.
+pub struct PlanarFramesMut<'a, 's, S: Sample> {
+    channels: &'a mut [&'s mut [S]],
+    frame_count: usize,
+}
+
+impl<'a, 's, S: Sample> PlanarFramesMut<'a, 's, S> {
+    /// This will panic if there are no channels, more than `MAX_CHANNELS` channels or if the
+    /// channels are not all the same length.
+    pub fn new(channels: &'a mut [&'s mut [S]]) -> PlanarFramesMut<'a, 's, S> {
+        let frame_count = planar_frame_count(channels.iter().map(|channel| channel.len()));
+        PlanarFramesMut {
+            channels,
+            frame_count,
+        }
+    }
+
+    /// Returns the samples of the channel at `index`. This will panic if `index` is out of bounds.
+    #[inline]
+    pub fn channel(&self, index: usize) -> &[S] {
+        self.channels[index]
+    }
+
+    /// Returns the samples of the channel at `index`. This will panic if `index` is out of bounds.
+    #[inline]
+    pub fn channel_mut(&mut self, index: usize) -> &mut [S] {
+        self.channels[index]
+    }
+
+    #[inline]
+    pub fn as_planar(&self) -> PlanarFrames<'_, S> {
+        // `&mut [S]` and `&[S]` have the same layout, so this just reborrows every channel.
+        let channels = unsafe {
+            std::slice::from_raw_parts(self.channels.as_ptr() as *const &[S], self.channels.len())
+        };
+        PlanarFrames {
+            channels,
+            frame_count: self.frame_count,
+        }
+    }
+
+    /// Deinterleaves `src` into these frames. `src` must have the same format, channel count and
+    /// frame count as these frames.
+    pub fn deinterleave_from(&mut self, src: &Frames) -> Result<(), Error> {
+        ensure_planar_compat(
+            (src.format(), src.channels(), src.frame_count()),
+            (self.format(), self.channels(), self.frame_count()),
+        )?;
+
+        let mut channel_ptrs = [std::ptr::null_mut(); MAX_CHANNELS];
+        for (ptr, channel) in channel_ptrs.iter_mut().zip(self.channels.iter_mut()) {
+            *ptr = channel.as_mut_ptr() as *mut _;
+        }
+
+        unsafe {
+            sys::ma_deinterleave_pcm_frames(
+                S::format() as _,
+                self.channels(),
+                self.frame_count as u64,
+                src.as_bytes().as_ptr() as *const _,
+                channel_ptrs.as_mut_ptr(),
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Returns the number of frames contained, which is the length of each channel.
+    #[inline]
+    pub fn frame_count(&self) -> usize {
+        self.frame_count
+    }
+
+    #[inline]
+    pub fn format(&self) -> Format {
+        S::format()
+    }
+
+    #[inline]
+    pub fn channels(&self) -> u32 {
+        self.channels.len() as u32
+    }
+}
+
+/// Returns the shared length of all of the channels.
+fn planar_frame_count(mut lengths: impl ExactSizeIterator<Item = usize>) -> usize {
+    let channel_count = lengths.len();
+    assert!(
+        channel_count > 0 && channel_count <= MAX_CHANNELS,
+        "invalid number of channels for planar frames (channels: {}, max: {})",
+        channel_count,
+        MAX_CHANNELS
+    );
+
+    let frame_count = lengths.next().unwrap_or(0);
+    assert!(
+        lengths.all(|len| len == frame_count),
+        "all of the channels of planar frames must have the same length"
+    );
+    frame_count
+}
+
+/// Checks that interleaved frames described by `(format, channels, frame_count)` match the planar
+/// frames that they are being converted to or from.
+fn ensure_planar_compat(
+    interleaved: (Format, u32, usize),
+    planar: (Format, u32, usize),
+) -> Result<(), Error> {
+    if interleaved != planar {
+        ma_debug_panic!(
+            "interleaved and planar frames did not match (interleaved: {:?}, planar: {:?})",
+            interleaved,
+            planar
+        );
+        Err(Error::InvalidArgs)
+    } else {
+        Ok(())
+    }
+}
@@ -0,0 +1,121 @@
+use crate::base::Error;
+use crate::channel_conv::ChannelConverter;
+use crate::data_conv::DataConverter;
+use crate::filters::Filter;
+use crate::frames::{Frames, FramesMut};
+use crate::resampling::{LinearResampler, Resampler};
+
+/// The number of frames that were consumed and produced by a call to `Processor::process`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ProcessedFrames {
+    /// The number of frames that were read from the input.
+    pub input_frames: u64,
+
+    /// The number of frames that were written to the output.
+    pub output_frames: u64,
+}
+
+/// Something that turns input frames into output frames, where the number of frames that are
+/// consumed and produced may differ. This is implemented by the resamplers and converters as well
+/// as every `Filter`, so they can all be used in the same processing pipeline.
+pub trait Processor {
+    /// Processes as many frames from `input` as will fit into `output`. Frames that were not
+    /// consumed have to be passed in again on the next call.
+    fn process(&mut self, output: &mut FramesMut, input: &Frames)
+        -> Result<ProcessedFrames, Error>;
+
+    /// The number of input frames that are needed to produce `output_frame_count` frames.
+    fn required_input_frame_count(&self, output_frame_count: u64) -> u64 {
+        output_frame_count
+    }
+
+    /// The number of output frames that will be produced from `input_frame_count` frames.
+    fn expected_output_frame_count(&self, input_frame_count: u64) -> u64 {
+        input_frame_count
+    }
+}
+
+/// Filters always produce one output frame for every input frame, so this processes as many frames
+/// as both `output` and `input` can hold.
+impl<F: Filter + ?Sized> Processor for F {
+    fn process(
+        &mut self,
+        output: &mut FramesMut,
+        input: &Frames,
+    ) -> Result<ProcessedFrames, Error> {
+        let frame_count = output.frame_count().min(input.frame_count());
+        let input = input_prefix(input, frame_count);
+        let mut output = output_prefix(output, frame_count);
+        self.process_pcm_frames(&mut output, &input)?;
+
+        Ok(ProcessedFrames {
+            input_frames: frame_count as u64,
+            output_frames: frame_count as u64,
+        })
+    }
+}
+
+impl Processor for ChannelConverter {
+    fn process(
+        &mut self,
+        output: &mut FramesMut,
+        input: &Frames,
+    ) -> Result<ProcessedFrames, Error> {
+        let frame_count = output.frame_count().min(input.frame_count());
+        let input = input_prefix(input, frame_count);
+        let mut output = output_prefix(output, frame_count);
+        self.process_pcm_frames(&mut output, &input)?;
+
+        Ok(ProcessedFrames {
+            input_frames: frame_count as u64,
+            output_frames: frame_count as u64,
+        })
+    }
+}
+
+macro_rules! impl_variable_rate_processor {
+    ($Type:ty) => {
+        impl Processor for $Type {
+            fn process(
+                &mut self,
+                output: &mut FramesMut,
+                input: &Frames,
+            ) -> Result<ProcessedFrames, Error> {
+                let (input_frames, output_frames) = self.process_pcm_frames(output, input)?;
+                Ok(ProcessedFrames {
+                    input_frames,
+                    output_frames,
+                })
+            }
+
+            fn required_input_frame_count(&self, output_frame_count: u64) -> u64 {
+                <$Type>::required_input_frame_count(self, output_frame_count)
+            }
+
+            fn expected_output_frame_count(&self, input_frame_count: u64) -> u64 {
+                <$Type>::expected_output_frame_count(self, input_frame_count)
+            }
+        }
+    };
+}
+
+impl_variable_rate_processor!(LinearResampler);
+impl_variable_rate_processor!(Resampler);
+impl_variable_rate_processor!(DataConverter);
+
+/// The first `frame_count` frames of `input`.
+fn input_prefix<'a>(input: &'a Frames, frame_count: usize) -> Frames<'a> {
+    let byte_count = frame_count * input.format().size_in_bytes() * input.channels() as usize;
+    Frames::wrap::<u8>(
+        &input.as_bytes()[..byte_count],
+        input.format(),
+        input.channels(),
+    )
+}
+
+/// The first `frame_count` frames of `output`.
+fn output_prefix<'a>(output: &'a mut FramesMut, frame_count: usize) -> FramesMut<'a> {
+    let (format, channels) = (output.format(), output.channels());
+    let byte_count = frame_count * format.size_in_bytes() * channels as usize;
+    FramesMut::wrap::<u8>(&mut output.as_bytes_mut()[..byte_count], format, channels)
+}
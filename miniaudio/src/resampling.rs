@@ -163,7 +163,7 @@ impl LinearResampler {
             )
         })?;
 
-        Ok((output_frames, input_frames))
+        Ok((input_frames, output_frames))
     }
 
     /// Sets the input and output sample rate.
@@ -211,9 +211,9 @@ impl LinearResampler {
             )
         }
     }
-    #[inline]
 
     /// Retrieves the latency introduced by the resampler in input frames.
+    #[inline]
     pub fn input_latency(&mut self) -> u64 {
         unsafe { sys::ma_linear_resampler_get_input_latency(&self.0 as *const _ as *mut _) }
     }
@@ -442,23 +442,23 @@ impl Resampler {
     #[inline]
     pub fn expected_output_frame_count(&self, input_frame_count: u64) -> u64 {
         unsafe {
-            sys::ma_linear_resampler_get_expected_output_frame_count(
+            sys::ma_resampler_get_expected_output_frame_count(
                 &self.0 as *const _ as *mut _,
                 input_frame_count,
             )
         }
     }
-    #[inline]
 
     /// Retrieves the latency introduced by the resampler in input frames.
+    #[inline]
     pub fn input_latency(&mut self) -> u64 {
-        unsafe { sys::ma_linear_resampler_get_input_latency(&self.0 as *const _ as *mut _) }
+        unsafe { sys::ma_resampler_get_input_latency(&self.0 as *const _ as *mut _) }
     }
 
     /// Retrieves the latency introduced by the resampler in output frames.
     #[inline]
     pub fn output_latency(&mut self) -> u64 {
-        unsafe { sys::ma_linear_resampler_get_output_latency(&self.0 as *const _ as *mut _) }
+        unsafe { sys::ma_resampler_get_output_latency(&self.0 as *const _ as *mut _) }
     }
 }
 
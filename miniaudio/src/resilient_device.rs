@@ -0,0 +1,409 @@
+use crate::base::Error;
+use crate::device_io::{Context, Device, DeviceConfig, DeviceControlError, RawDevice};
+use crate::frames::{Frames, FramesMut};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// The longest amount of time to wait for a lost device to finish stopping before it is closed.
+const STOP_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Something that happened while a `ResilientDevice` was recovering from an unexpected stop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReconnectEvent {
+    /// The device stopped without `ResilientDevice::stop` being called, e.g. because it was
+    /// unplugged.
+    DeviceLost,
+
+    /// A new device was opened with the same config. If the configured device could not be opened
+    /// and the policy allows it, the default device is opened instead, in which case
+    /// `used_default_device` is true.
+    Reconnected { used_default_device: bool },
+
+    /// An attempt to open or start a new device failed. Another attempt is made after the retry
+    /// interval unless the maximum number of attempts has been reached.
+    ReconnectFailed(Error),
+
+    /// The maximum number of attempts was reached without opening a new device. The device stays
+    /// closed until `ResilientDevice::reconnect` is called.
+    GaveUp,
+}
+
+/// Controls how a `ResilientDevice` reopens its device after an unexpected stop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReconnectPolicy {
+    /// The time to wait after a failed attempt before trying again.
+    pub retry_interval: Duration,
+
+    /// The number of attempts to make before giving up, or `None` to keep trying forever.
+    pub max_attempts: Option<u32>,
+
+    /// If the config names a specific device and that device can't be opened, open the default
+    /// device instead.
+    pub fallback_to_default: bool,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> ReconnectPolicy {
+        ReconnectPolicy {
+            retry_interval: Duration::from_millis(500),
+            max_attempts: None,
+            fallback_to_default: true,
+        }
+    }
+}
+
+type BoxedEventCallback = Box<dyn FnMut(ReconnectEvent) + Send>;
+type SharedDataCallback = Arc<Mutex<Box<dyn FnMut(&RawDevice, &mut FramesMut, &Frames) + Send>>>;
+
+enum Message {
+    /// The device with the given generation stopped unexpectedly.
+    Lost(u64),
+    Reconnect,
+    Shutdown,
+}
+
+struct Shared {
+    context: Option<Context>,
+    config: Mutex<DeviceConfig>,
+    // NOTE: `running` is only changed while this is locked, and a new device is opened, started and
+    // stored while this is locked, so a reopened device always matches the last `start` or `stop`.
+    device: Mutex<Option<Device>>,
+    data_callback: SharedDataCallback,
+    event_callback: Mutex<Option<BoxedEventCallback>>,
+    policy: Mutex<ReconnectPolicy>,
+
+    /// The generation of the current device, which is incremented every time a device is opened.
+    /// This is used to ignore stops from devices that were already replaced.
+    generation: Mutex<u64>,
+
+    /// True if the device should be running. A stop while this is set is unexpected.
+    running: Arc<AtomicBool>,
+}
+
+/// A device that reopens itself when it stops unexpectedly, e.g. because it was unplugged or
+/// because the default device changed. The device is reopened with the same config and the same
+/// data callback, first using the configured device and then, if the policy allows it, the default
+/// device. Reopening happens on a supervisor thread owned by this device, which also reports what
+/// happened through the event callback.
+///
+/// A `ResilientDevice` installs its own data and stop callbacks on every device that it opens, so
+/// the data and stop callbacks of the config are never called. The state callback of the config is
+/// used by every device.
+pub struct ResilientDevice {
+    shared: Arc<Shared>,
+    sender: Sender<Message>,
+    supervisor: Option<JoinHandle<()>>,
+}
+
+impl ResilientDevice {
+    /// Opens a device with the given config. The device is not started until `start` is called.
+    pub fn new<F>(
+        context: Option<Context>,
+        config: DeviceConfig,
+        data_callback: F,
+    ) -> Result<ResilientDevice, Error>
+    where
+        F: FnMut(&RawDevice, &mut FramesMut, &Frames) + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel();
+        let shared = Arc::new(Shared {
+            context,
+            config: Mutex::new(config),
+            device: Mutex::new(None),
+            data_callback: Arc::new(Mutex::new(Box::new(data_callback))),
+            event_callback: Mutex::new(None),
+            policy: Mutex::new(ReconnectPolicy::default()),
+            generation: Mutex::new(0),
+            running: Arc::new(AtomicBool::new(false)),
+        });
+
+        let device = shared.open(&sender, false)?;
+        *shared.device.lock().expect("device lock poisoned") = Some(device);
+
+        let supervisor = {
+            let shared = Arc::clone(&shared);
+            let sender = sender.clone();
+            std::thread::Builder::new()
+                .name("miniaudio-resilient-device".to_string())
+                .spawn(move || supervise(shared, sender, receiver))
+                .map_err(|_| Error::Generic)?
+        };
+
+        Ok(ResilientDevice {
+            shared,
+            sender,
+            supervisor: Some(supervisor),
+        })
+    }
+
+    /// Sets the function that is called from the supervisor thread whenever something happens
+    /// while recovering the device.
+    pub fn set_event_callback<F>(&self, callback: F)
+    where
+        F: FnMut(ReconnectEvent) + Send + 'static,
+    {
+        *self
+            .shared
+            .event_callback
+            .lock()
+            .expect("event callback lock poisoned") = Some(Box::new(callback));
+    }
+
+    pub fn reconnect_policy(&self) -> ReconnectPolicy {
+        *self.shared.policy.lock().expect("policy lock poisoned")
+    }
+
+    pub fn set_reconnect_policy(&self, policy: ReconnectPolicy) {
+        *self.shared.policy.lock().expect("policy lock poisoned") = policy;
+    }
+
+    /// Returns the device that is currently open. This returns `None` while the device is being
+    /// reopened or after giving up.
+    ///
+    /// NOTE: Stopping the returned device directly counts as an unexpected stop, so it will be
+    /// reopened. This blocks while a new device is being opened, so it should not be called from
+    /// the callbacks of the device.
+    pub fn device(&self) -> Option<Device> {
+        self.shared
+            .device
+            .lock()
+            .expect("device lock poisoned")
+            .clone()
+    }
+
+    /// Returns true if the device should be running. This stays true while a lost device is being
+    /// reopened.
+    pub fn is_running(&self) -> bool {
+        self.shared.running.load(Ordering::Acquire)
+    }
+
+    /// Starts the device. If the device is currently being reopened it will be started as soon as
+    /// it is open again.
+    ///
+    /// **WARNING** This should not be called from a callback.
+    pub fn start(&self) -> Result<(), DeviceControlError> {
+        let device = self.shared.device.lock().expect("device lock poisoned");
+        self.shared.running.store(true, Ordering::Release);
+        match *device {
+            Some(ref device) => device.start().inspect_err(|_| {
+                self.shared.running.store(false, Ordering::Release);
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Stops the device. This does not count as an unexpected stop, so the device will not be
+    /// reopened.
+    ///
+    /// **WARNING** This should not be called from a callback.
+    pub fn stop(&self) -> Result<(), DeviceControlError> {
+        let device = self.shared.device.lock().expect("device lock poisoned");
+        self.shared.running.store(false, Ordering::Release);
+        match *device {
+            Some(ref device) => device.stop(),
+            None => Ok(()),
+        }
+    }
+
+    /// Asks the supervisor thread to reopen the device, e.g. after it gave up or to switch to a new
+    /// default device. The device is started again if it was running.
+    pub fn reconnect(&self) {
+        let _ = self.sender.send(Message::Reconnect);
+    }
+
+    /// Makes the current device look like it was stopped by the backend. The supervisor is told
+    /// that the device was lost while it is still `Started`, which is what happens while the stop
+    /// callback of a backend-initiated stop is running, and the device only becomes `Stopped` after
+    /// `delay`. This is meant for checking the reconnect logic without a device to unplug.
+    #[doc(hidden)]
+    pub fn simulate_backend_stop(&self, delay: Duration) {
+        let device = self.shared.device.lock().expect("device lock poisoned");
+        let device = match *device {
+            Some(ref device) if self.is_running() => device.clone(),
+            _ => return,
+        };
+
+        let generation = *self
+            .shared
+            .generation
+            .lock()
+            .expect("generation lock poisoned");
+        let _ = self.sender.send(Message::Lost(generation));
+
+        // NOTE: The stop callback also reports this stop, but by then the device has been replaced
+        // so it is ignored.
+        std::thread::spawn(move || {
+            std::thread::sleep(delay);
+            let _ = device.stop();
+        });
+    }
+}
+
+impl Drop for ResilientDevice {
+    fn drop(&mut self) {
+        self.shared.running.store(false, Ordering::Release);
+        let _ = self.sender.send(Message::Shutdown);
+        if let Some(supervisor) = self.supervisor.take() {
+            let _ = supervisor.join();
+        }
+    }
+}
+
+impl Shared {
+    /// Opens a new device and installs the callbacks on it. The device is started if `start` is
+    /// true.
+    fn open(&self, sender: &Sender<Message>, start: bool) -> Result<Device, Error> {
+        let mut generation = self.generation.lock().expect("generation lock poisoned");
+        *generation += 1;
+
+        let config = self.config.lock().expect("config lock poisoned");
+        let mut device = Device::new(self.context.clone(), &config)?;
+        drop(config);
+
+        let data_callback = Arc::clone(&self.data_callback);
+        device.set_data_callback(move |device, output, input| {
+            if let Ok(mut data_callback) = data_callback.lock() {
+                (data_callback)(device, output, input);
+            }
+        });
+
+        let (running, sender, device_generation) =
+            (Arc::clone(&self.running), sender.clone(), *generation);
+        device.set_stop_callback(move |_device| {
+            if running.load(Ordering::Acquire) {
+                let _ = sender.send(Message::Lost(device_generation));
+            }
+        });
+
+        if start {
+            device.start().map_err(Error::from)?;
+        }
+        Ok(device)
+    }
+
+    /// Opens the configured device, falling back to the default device if allowed. Returns the
+    /// device and whether or not the default device was used.
+    ///
+    /// This takes the lock of the device slot so that `running` can't change until the new device
+    /// has been stored in it.
+    fn open_with_fallback(
+        &self,
+        _device: &MutexGuard<Option<Device>>,
+        sender: &Sender<Message>,
+        fallback_to_default: bool,
+    ) -> Result<(Device, bool), Error> {
+        let start = self.running.load(Ordering::Acquire);
+        let err = match self.open(sender, start) {
+            Ok(device) => return Ok((device, false)),
+            Err(err) => err,
+        };
+
+        let mut config = self.config.lock().expect("config lock poisoned");
+        let playback_id = config.playback().device_id().cloned();
+        let capture_id = config.capture().device_id().cloned();
+        if !fallback_to_default || (playback_id.is_none() && capture_id.is_none()) {
+            return Err(err);
+        }
+
+        config.playback_mut().set_device_id(None);
+        config.capture_mut().set_device_id(None);
+        drop(config);
+
+        let result = self.open(sender, start);
+
+        let mut config = self.config.lock().expect("config lock poisoned");
+        config.playback_mut().set_device_id(playback_id);
+        config.capture_mut().set_device_id(capture_id);
+
+        result.map(|device| (device, true))
+    }
+
+    fn emit(&self, event: ReconnectEvent) {
+        if let Ok(mut event_callback) = self.event_callback.lock() {
+            if let Some(ref mut event_callback) = *event_callback {
+                (event_callback)(event);
+            }
+        }
+    }
+
+    fn is_current(&self, generation: u64) -> bool {
+        *self.generation.lock().expect("generation lock poisoned") == generation
+    }
+}
+
+fn supervise(shared: Arc<Shared>, sender: Sender<Message>, receiver: Receiver<Message>) {
+    while let Ok(message) = receiver.recv() {
+        match message {
+            Message::Lost(generation) if shared.is_current(generation) => {
+                shared.emit(ReconnectEvent::DeviceLost);
+                if !reconnect(&shared, &sender, &receiver) {
+                    return;
+                }
+            }
+            Message::Lost(_) => {}
+            Message::Reconnect => {
+                if !reconnect(&shared, &sender, &receiver) {
+                    return;
+                }
+            }
+            Message::Shutdown => return,
+        }
+    }
+}
+
+/// Closes the current device and keeps trying to open a new one according to the policy. This
+/// returns false if the supervisor was shut down while reconnecting.
+fn reconnect(shared: &Shared, sender: &Sender<Message>, receiver: &Receiver<Message>) -> bool {
+    let old_device = shared.device.lock().expect("device lock poisoned").take();
+    if let Some(old_device) = old_device {
+        // The device can't be closed until miniaudio has finished stopping it. When the backend
+        // stopped the device it is still `Started` while the stop callback runs and is only marked
+        // as `Stopped` after it returns, so this has to wait for `Stopped` specifically.
+        old_device.wait_until_stopped(STOP_TIMEOUT);
+        drop(old_device);
+    }
+
+    let mut attempts = 0;
+    loop {
+        let policy = *shared.policy.lock().expect("policy lock poisoned");
+        attempts += 1;
+
+        let mut device_slot = shared.device.lock().expect("device lock poisoned");
+        let result = shared
+            .open_with_fallback(&device_slot, sender, policy.fallback_to_default)
+            .map(|(device, used_default_device)| {
+                *device_slot = Some(device);
+                used_default_device
+            });
+
+        // NOTE: The lock must be released before waiting for the next attempt so that `start` and
+        // `stop` don't block for the whole retry interval.
+        drop(device_slot);
+
+        match result {
+            Ok(used_default_device) => {
+                shared.emit(ReconnectEvent::Reconnected {
+                    used_default_device,
+                });
+                return true;
+            }
+            Err(err) => shared.emit(ReconnectEvent::ReconnectFailed(err)),
+        }
+
+        if policy.max_attempts.is_some_and(|max| attempts >= max) {
+            shared.emit(ReconnectEvent::GaveUp);
+            return true;
+        }
+
+        // Waiting on the channel instead of sleeping so that a shutdown isn't delayed by the retry
+        // interval. Stops from devices that failed to start and repeated reconnect requests are
+        // covered by this attempt.
+        match receiver.recv_timeout(policy.retry_interval) {
+            Ok(Message::Shutdown) | Err(RecvTimeoutError::Disconnected) => return false,
+            Ok(_) | Err(RecvTimeoutError::Timeout) => {}
+        }
+    }
+}
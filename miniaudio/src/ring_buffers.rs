@@ -1,12 +1,15 @@
 //! Miniaudio supports lock free (single producer, single consumer) ring buffers which are exposed
-//! via the `RingBuffer` and `PCMRingBuffer` APIs. The `RingBuffer` API operats on bytes, whereas
-//! the `PCMRingBuffer` operates on PCM frames. They are otherwise identical as `PCMRingBuffer` is
+//! via the `RingBuffer` and `PcmRingBuffer` APIs. The `RingBuffer` API operats on bytes, whereas
+//! the `PcmRingBuffer` operates on PCM frames. They are otherwise identical as `PcmRingBuffer` is
 //! just a wrapper around `RingBuffer`.
 
-use crate::base::{from_bool8, Error};
+use crate::base::{from_bool8, Error, Format};
+use crate::device_io::{DeviceConfig, RawDevice};
+use crate::frames::{Frames, FramesMut};
 use miniaudio_sys as sys;
 use std::os::raw::c_void;
 use std::ptr::NonNull;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 
 #[repr(transparent)]
@@ -124,13 +127,10 @@ impl<T: Clone> RingBuffer<T> {
         )
     }
 
-    /// Used to retrieve a section of the ring buffer for reading. You specify the number of items
-    /// you would like to read and a slice with the number of requested items (or less if the
-    /// buffer needs to wrap), will be passed to the given closure.
-    pub(crate) fn read<F>(&self, count_requested: usize, f: F) -> usize
-    where
-        F: FnOnce(&[T]),
-    {
+    /// Acquires a contiguous section of the ring buffer for reading. This will return a pointer to
+    /// the section and the number of items that it contains, which may be less than the number
+    /// requested if there is not enough data available or if the buffer needs to wrap.
+    pub(crate) fn acquire_read(&self, count_requested: usize) -> (*mut T, usize) {
         let mut bytes = count_requested * std::mem::size_of::<T>();
         let mut buf_ptr: *mut c_void = std::ptr::null_mut();
         let acquire_result = unsafe {
@@ -141,22 +141,80 @@ impl<T: Clone> RingBuffer<T> {
         debug_assert!(acquire_result == 0);
         debug_assert!(bytes % std::mem::size_of::<T>() == 0);
 
-        let count = bytes / std::mem::size_of::<T>();
+        if buf_ptr.is_null() {
+            return (std::ptr::null_mut(), 0);
+        }
+
+        (buf_ptr.cast::<T>(), bytes / std::mem::size_of::<T>())
+    }
+
+    /// Commits `count` items of a section that was acquired using `acquire_read`, moving the read
+    /// pointer forward.
+    pub(crate) fn commit_read(&self, count: usize, buf_ptr: *mut T) -> Result<(), Error> {
+        Error::from_c_result(unsafe {
+            sys::ma_rb_commit_read(
+                &self.inner as *const _ as *mut _,
+                count * std::mem::size_of::<T>(),
+                buf_ptr.cast(),
+            )
+        })
+    }
+
+    /// Acquires a contiguous section of the ring buffer for writing. This will return a pointer to
+    /// the section and the number of items that it contains, which may be less than the number
+    /// requested if there is not enough space available or if the buffer needs to wrap.
+    pub(crate) fn acquire_write(&self, count_requested: usize) -> (*mut T, usize) {
+        let mut bytes = count_requested * std::mem::size_of::<T>();
+        let mut buf_ptr: *mut c_void = std::ptr::null_mut();
+        let acquire_result = unsafe {
+            sys::ma_rb_acquire_write(&self.inner as *const _ as *mut _, &mut bytes, &mut buf_ptr)
+        };
+
+        // This shouldn't fail because our arguments are valid, but we debug assert just to be sure.
+        debug_assert!(acquire_result == 0);
+        debug_assert!(bytes % std::mem::size_of::<T>() == 0);
+
+        if buf_ptr.is_null() {
+            return (std::ptr::null_mut(), 0);
+        }
+
+        (buf_ptr.cast::<T>(), bytes / std::mem::size_of::<T>())
+    }
+
+    /// Commits `count` items of a section that was acquired using `acquire_write`, moving the
+    /// write pointer forward.
+    pub(crate) fn commit_write(&self, count: usize, buf_ptr: *mut T) -> Result<(), Error> {
+        Error::from_c_result(unsafe {
+            sys::ma_rb_commit_write(
+                &self.inner as *const _ as *mut _,
+                count * std::mem::size_of::<T>(),
+                buf_ptr.cast(),
+            )
+        })
+    }
+
+    /// Used to retrieve a section of the ring buffer for reading. You specify the number of items
+    /// you would like to read and a slice with the number of requested items (or less if the
+    /// buffer needs to wrap), will be passed to the given closure.
+    pub(crate) fn read<F>(&self, count_requested: usize, f: F) -> usize
+    where
+        F: FnOnce(&[T]),
+    {
+        let (buf_ptr, count) = self.acquire_read(count_requested);
 
-        if count == 0 || buf_ptr.is_null() {
+        if count == 0 {
             f(&[]);
             return 0;
         }
 
-        let items = unsafe { std::slice::from_raw_parts(buf_ptr.cast::<T>(), count) };
+        let items = unsafe { std::slice::from_raw_parts(buf_ptr, count) };
 
         f(items);
 
-        let commit_result =
-            unsafe { sys::ma_rb_commit_read(&self.inner as *const _ as *mut _, bytes, buf_ptr) };
+        let commit_result = self.commit_read(count, buf_ptr);
 
         // This shouldn't fail because our arguments are valid, but we debug assert just to be sure.
-        debug_assert!(commit_result == 0);
+        debug_assert!(commit_result.is_ok());
 
         count
     }
@@ -168,42 +226,61 @@ impl<T: Clone> RingBuffer<T> {
     where
         F: FnOnce(&mut [T]),
     {
-        let mut bytes = count_requested * std::mem::size_of::<T>();
-        let mut buf_ptr: *mut c_void = std::ptr::null_mut();
-        let acquire_result = unsafe {
-            sys::ma_rb_acquire_write(&self.inner as *const _ as *mut _, &mut bytes, &mut buf_ptr)
-        };
-
-        // This shouldn't fail because our arguments are valid, but we debug assert just to be sure.
-        debug_assert!(acquire_result == 0);
-        debug_assert!(bytes % std::mem::size_of::<T>() == 0);
+        let (buf_ptr, count) = self.acquire_write(count_requested);
 
-        let count = bytes / std::mem::size_of::<T>();
-
-        if count == 0 || buf_ptr.is_null() {
+        if count == 0 {
             f(&mut []);
             return 0;
         }
 
-        let items = unsafe { std::slice::from_raw_parts_mut(buf_ptr.cast::<T>(), count) };
+        let items = unsafe { std::slice::from_raw_parts_mut(buf_ptr, count) };
 
         f(items);
 
-        let commit_result =
-            unsafe { sys::ma_rb_commit_write(&self.inner as *const _ as *mut _, bytes, buf_ptr) };
+        let commit_result = self.commit_write(count, buf_ptr);
 
         // This shouldn't fail because our arguments are valid, but we debug assert just to be sure.
-        debug_assert!(commit_result == 0);
+        debug_assert!(commit_result.is_ok());
 
         count
     }
 
-    // FIXME find out what to do with this and remove allow(dead_code).
+    /// Moves the read pointer forward by `count` items without reading them.
+    #[inline]
+    pub(crate) fn seek_read(&self, count: usize) -> Result<(), Error> {
+        Error::from_c_result(unsafe {
+            sys::ma_rb_seek_read(
+                &self.inner as *const _ as *mut _,
+                count * std::mem::size_of::<T>(),
+            )
+        })
+    }
+
+    /// Moves the write pointer forward by `count` items without writing to them.
+    #[inline]
+    pub(crate) fn seek_write(&self, count: usize) -> Result<(), Error> {
+        Error::from_c_result(unsafe {
+            sys::ma_rb_seek_write(
+                &self.inner as *const _ as *mut _,
+                count * std::mem::size_of::<T>(),
+            )
+        })
+    }
+
+    /// Moves both the read and write pointers back to the start of the buffer.
+    ///
+    /// # Safety
+    /// This is not thread-safe and must not be called while the buffer is being read from or
+    /// written to.
+    #[inline]
+    pub(crate) unsafe fn reset(&self) {
+        sys::ma_rb_reset(&self.inner as *const _ as *mut _);
+    }
+
     /// Returns the distance between the write pointer and the read pointer. Should never be
     /// negative for a correct program. Will return the number of items that can be read before the
     /// read pointer hits the write pointer.
     #[inline]
-    #[allow(dead_code)]
     pub(crate) fn pointer_distance(&self) -> usize {
         let byte_distance =
             unsafe { sys::ma_rb_pointer_distance(&self.inner as *const _ as *mut _) as usize };
@@ -248,9 +325,6 @@ impl<T: Clone> RingBuffer<T> {
     pub(crate) fn subbuffer_offset(&self, index: usize) -> usize {
         unsafe { sys::ma_rb_get_subbuffer_offset(&self.inner as *const _ as *mut _, index) }
     }
-
-    // FIXME implement the seek_read and seek_write functions when I figure out what those are for
-    // really.
 }
 
 unsafe impl<T: Send + Sized + Clone> Send for RingBuffer<T> {}
@@ -283,6 +357,41 @@ impl<T: Clone> RingBufferSend<T> {
         self.inner.write(count_requested, f)
     }
 
+    /// Acquires a contiguous section of the ring buffer for writing that can be held across calls.
+    /// The returned guard will contain the number of requested items, or less if there is not
+    /// enough space or the buffer needs to wrap. If the buffer wrapped, the remaining items can be
+    /// acquired after this section has been committed.
+    ///
+    /// The section is committed when the guard is dropped, or explicitly using
+    /// `RingBufferWriteGuard::commit`.
+    pub fn acquire_write(&mut self, count_requested: usize) -> RingBufferWriteGuard<'_, T> {
+        let (ptr, len) = self.inner.acquire_write(count_requested);
+        RingBufferWriteGuard {
+            inner: &self.inner,
+            ptr,
+            len,
+        }
+    }
+
+    /// Moves the write pointer forward by `count` items without writing to them.
+    pub fn seek_write(&mut self, count: usize) -> Result<(), Error> {
+        self.inner.seek_write(count)
+    }
+
+    /// Moves both the read and write pointers back to the start of the buffer.
+    ///
+    /// # Safety
+    /// This is not thread-safe and must not be called while the receiving end of the buffer is
+    /// being read from.
+    pub unsafe fn reset(&mut self) {
+        self.inner.reset()
+    }
+
+    /// Returns the number of items between the write pointer and the read pointer.
+    pub fn pointer_distance(&self) -> usize {
+        self.inner.pointer_distance()
+    }
+
     /// Returns the number of items that are available for writing.
     pub fn available(&mut self) -> usize {
         self.inner.available_write()
@@ -324,6 +433,41 @@ impl<T: Clone> RingBufferRecv<T> {
         self.inner.read(count_requested, f)
     }
 
+    /// Acquires a contiguous section of the ring buffer for reading that can be held across calls.
+    /// The returned guard will contain the number of requested items, or less if there is not
+    /// enough data available or the buffer needs to wrap. If the buffer wrapped, the remaining
+    /// items can be acquired after this section has been committed.
+    ///
+    /// The section is committed when the guard is dropped, or explicitly using
+    /// `RingBufferReadGuard::commit`.
+    pub fn acquire_read(&mut self, count_requested: usize) -> RingBufferReadGuard<'_, T> {
+        let (ptr, len) = self.inner.acquire_read(count_requested);
+        RingBufferReadGuard {
+            inner: &self.inner,
+            ptr,
+            len,
+        }
+    }
+
+    /// Moves the read pointer forward by `count` items without reading them.
+    pub fn seek_read(&mut self, count: usize) -> Result<(), Error> {
+        self.inner.seek_read(count)
+    }
+
+    /// Moves both the read and write pointers back to the start of the buffer.
+    ///
+    /// # Safety
+    /// This is not thread-safe and must not be called while the sending end of the buffer is
+    /// being written to.
+    pub unsafe fn reset(&mut self) {
+        self.inner.reset()
+    }
+
+    /// Returns the number of items between the write pointer and the read pointer.
+    pub fn pointer_distance(&self) -> usize {
+        self.inner.pointer_distance()
+    }
+
     /// Returns the number of items that are available for reading.
     pub fn available(&mut self) -> usize {
         self.inner.available_read()
@@ -338,6 +482,131 @@ impl<T: Clone> Clone for RingBufferRecv<T> {
     }
 }
 
+/// A section of a ring buffer that has been acquired for writing. This dereferences to a mutable
+/// slice of the acquired items and will commit all of them when dropped.
+pub struct RingBufferWriteGuard<'a, T: Clone> {
+    inner: &'a RingBuffer<T>,
+    ptr: *mut T,
+    len: usize,
+}
+
+impl<'a, T: Clone> RingBufferWriteGuard<'a, T> {
+    /// Commits the entire acquired section, making it available to the reader.
+    pub fn commit(self) -> Result<(), Error> {
+        let len = self.len;
+        self.commit_count(len)
+    }
+
+    /// Commits only the first `count` items of the acquired section, making them available to the
+    /// reader. Committing 0 items will release the section without moving the write pointer.
+    pub fn commit_count(self, count: usize) -> Result<(), Error> {
+        assert!(
+            count <= self.len,
+            "committed more items than were acquired (committed: {}, acquired: {})",
+            count,
+            self.len
+        );
+
+        let result = self.commit_raw(count);
+        std::mem::forget(self);
+        result
+    }
+
+    fn commit_raw(&self, count: usize) -> Result<(), Error> {
+        if self.ptr.is_null() {
+            return Ok(());
+        }
+        self.inner.commit_write(count, self.ptr)
+    }
+}
+
+impl<'a, T: Clone> std::ops::Deref for RingBufferWriteGuard<'a, T> {
+    type Target = [T];
+
+    fn deref(&self) -> &Self::Target {
+        if self.ptr.is_null() {
+            return &[];
+        }
+        unsafe { std::slice::from_raw_parts(self.ptr, self.len) }
+    }
+}
+
+impl<'a, T: Clone> std::ops::DerefMut for RingBufferWriteGuard<'a, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        if self.ptr.is_null() {
+            return &mut [];
+        }
+        unsafe { std::slice::from_raw_parts_mut(self.ptr, self.len) }
+    }
+}
+
+impl<'a, T: Clone> Drop for RingBufferWriteGuard<'a, T> {
+    fn drop(&mut self) {
+        let commit_result = self.commit_raw(self.len);
+
+        // This shouldn't fail because our arguments are valid, but we debug assert just to be sure.
+        debug_assert!(commit_result.is_ok());
+    }
+}
+
+/// A section of a ring buffer that has been acquired for reading. This dereferences to a slice of
+/// the acquired items and will commit all of them when dropped.
+pub struct RingBufferReadGuard<'a, T: Clone> {
+    inner: &'a RingBuffer<T>,
+    ptr: *mut T,
+    len: usize,
+}
+
+impl<'a, T: Clone> RingBufferReadGuard<'a, T> {
+    /// Commits the entire acquired section, making it available to the writer.
+    pub fn commit(self) -> Result<(), Error> {
+        let len = self.len;
+        self.commit_count(len)
+    }
+
+    /// Commits only the first `count` items of the acquired section, making them available to the
+    /// writer. Committing 0 items will release the section without moving the read pointer.
+    pub fn commit_count(self, count: usize) -> Result<(), Error> {
+        assert!(
+            count <= self.len,
+            "committed more items than were acquired (committed: {}, acquired: {})",
+            count,
+            self.len
+        );
+
+        let result = self.commit_raw(count);
+        std::mem::forget(self);
+        result
+    }
+
+    fn commit_raw(&self, count: usize) -> Result<(), Error> {
+        if self.ptr.is_null() {
+            return Ok(());
+        }
+        self.inner.commit_read(count, self.ptr)
+    }
+}
+
+impl<'a, T: Clone> std::ops::Deref for RingBufferReadGuard<'a, T> {
+    type Target = [T];
+
+    fn deref(&self) -> &Self::Target {
+        if self.ptr.is_null() {
+            return &[];
+        }
+        unsafe { std::slice::from_raw_parts(self.ptr, self.len) }
+    }
+}
+
+impl<'a, T: Clone> Drop for RingBufferReadGuard<'a, T> {
+    fn drop(&mut self) {
+        let commit_result = self.commit_raw(self.len);
+
+        // This shouldn't fail because our arguments are valid, but we debug assert just to be sure.
+        debug_assert!(commit_result.is_ok());
+    }
+}
+
 impl<T: Clone> Drop for RingBuffer<T> {
     fn drop(&mut self) {
         unsafe {
@@ -384,3 +653,627 @@ pub fn ring_buffer_preallocated<T: Clone + Send>(
         preallocated,
     )
 }
+
+#[repr(transparent)]
+#[derive(Debug)]
+pub(crate) struct PcmRingBuffer {
+    inner: sys::ma_pcm_rb,
+}
+
+impl PcmRingBuffer {
+    pub(crate) fn split(self) -> (PcmRingBufferSend, PcmRingBufferRecv) {
+        let wrapped = Arc::new(self);
+        let recv = PcmRingBufferRecv {
+            inner: Arc::clone(&wrapped),
+        };
+        let send = PcmRingBufferSend { inner: wrapped };
+        (send, recv)
+    }
+
+    pub(crate) fn create_pair(
+        format: Format,
+        channels: u32,
+        buffer_size_in_frames: usize,
+    ) -> Result<(PcmRingBufferSend, PcmRingBufferRecv), Error> {
+        PcmRingBuffer::new(format, channels, buffer_size_in_frames).map(Self::split)
+    }
+
+    pub(crate) fn new(
+        format: Format,
+        channels: u32,
+        buffer_size_in_frames: usize,
+    ) -> Result<PcmRingBuffer, Error> {
+        let mut ring_buffer = std::mem::MaybeUninit::<sys::ma_pcm_rb>::uninit();
+
+        unsafe {
+            let result = sys::ma_pcm_rb_init(
+                format as _,
+                channels,
+                buffer_size_in_frames as u32,
+                std::ptr::null_mut(),
+                std::ptr::null(),
+                ring_buffer.as_mut_ptr(),
+            );
+
+            map_result!(
+                result,
+                PcmRingBuffer {
+                    inner: ring_buffer.assume_init(),
+                }
+            )
+        }
+    }
+
+    #[inline]
+    pub(crate) fn format(&self) -> Format {
+        Format::from_c(self.inner.format)
+    }
+
+    #[inline]
+    pub(crate) fn channels(&self) -> u32 {
+        self.inner.channels
+    }
+
+    /// Used to retrieve a section of the ring buffer for reading. You specify the number of frames
+    /// you would like to read and frames with the number of requested frames (or less if the
+    /// buffer needs to wrap), will be passed to the given closure.
+    pub(crate) fn read<F>(&self, frame_count_requested: usize, f: F) -> usize
+    where
+        F: FnOnce(&Frames),
+    {
+        let mut frame_count = frame_count_requested as u32;
+        let mut buf_ptr: *mut c_void = std::ptr::null_mut();
+        let acquire_result = unsafe {
+            sys::ma_pcm_rb_acquire_read(
+                &self.inner as *const _ as *mut _,
+                &mut frame_count,
+                &mut buf_ptr,
+            )
+        };
+
+        // This shouldn't fail because our arguments are valid, but we debug assert just to be sure.
+        debug_assert!(acquire_result == 0);
+
+        if frame_count == 0 || buf_ptr.is_null() {
+            f(&Frames::wrap::<u8>(&[], self.format(), self.channels()));
+            return 0;
+        }
+
+        let byte_count = frame_count as usize * self.bytes_per_frame();
+        let frames = Frames::wrap::<u8>(
+            unsafe { std::slice::from_raw_parts(buf_ptr.cast::<u8>(), byte_count) },
+            self.format(),
+            self.channels(),
+        );
+
+        f(&frames);
+
+        let commit_result = unsafe {
+            sys::ma_pcm_rb_commit_read(&self.inner as *const _ as *mut _, frame_count, buf_ptr)
+        };
+
+        // This shouldn't fail because our arguments are valid, but we debug assert just to be sure.
+        debug_assert!(commit_result == 0);
+
+        frame_count as usize
+    }
+
+    /// Used to retrieve a section of the ring buffer for writing. You specify the number of frames
+    /// you would like to write to and frames with the number of requested frames (or less if the
+    /// buffer needs to wrap), will be passed to the given closure.
+    pub(crate) fn write<F>(&self, frame_count_requested: usize, f: F) -> usize
+    where
+        F: FnOnce(&mut FramesMut),
+    {
+        let mut frame_count = frame_count_requested as u32;
+        let mut buf_ptr: *mut c_void = std::ptr::null_mut();
+        let acquire_result = unsafe {
+            sys::ma_pcm_rb_acquire_write(
+                &self.inner as *const _ as *mut _,
+                &mut frame_count,
+                &mut buf_ptr,
+            )
+        };
+
+        // This shouldn't fail because our arguments are valid, but we debug assert just to be sure.
+        debug_assert!(acquire_result == 0);
+
+        if frame_count == 0 || buf_ptr.is_null() {
+            f(&mut FramesMut::wrap::<u8>(
+                &mut [],
+                self.format(),
+                self.channels(),
+            ));
+            return 0;
+        }
+
+        let byte_count = frame_count as usize * self.bytes_per_frame();
+        let mut frames = FramesMut::wrap::<u8>(
+            unsafe { std::slice::from_raw_parts_mut(buf_ptr.cast::<u8>(), byte_count) },
+            self.format(),
+            self.channels(),
+        );
+
+        f(&mut frames);
+
+        let commit_result = unsafe {
+            sys::ma_pcm_rb_commit_write(&self.inner as *const _ as *mut _, frame_count, buf_ptr)
+        };
+
+        // This shouldn't fail because our arguments are valid, but we debug assert just to be sure.
+        debug_assert!(commit_result == 0);
+
+        frame_count as usize
+    }
+
+    #[inline]
+    pub(crate) fn available_read(&self) -> usize {
+        unsafe { sys::ma_pcm_rb_available_read(&self.inner as *const _ as *mut _) as usize }
+    }
+
+    #[inline]
+    pub(crate) fn available_write(&self) -> usize {
+        unsafe { sys::ma_pcm_rb_available_write(&self.inner as *const _ as *mut _) as usize }
+    }
+
+    #[inline]
+    pub(crate) fn bytes_per_frame(&self) -> usize {
+        self.format().size_in_bytes() * self.channels() as usize
+    }
+}
+
+impl Drop for PcmRingBuffer {
+    fn drop(&mut self) {
+        unsafe { sys::ma_pcm_rb_uninit(&mut self.inner) };
+    }
+}
+
+unsafe impl Send for PcmRingBuffer {}
+unsafe impl Sync for PcmRingBuffer {}
+
+/// Be aware that it is not safe to have this being written to from multiple threads.
+/// This is part of a **single producer** single consumer ring buffer.
+pub struct PcmRingBufferSend {
+    inner: Arc<PcmRingBuffer>,
+}
+
+impl PcmRingBufferSend {
+    /// Write frames into the ring buffer, returning the number of frames that were successfully
+    /// written. The format and channel count of `src` must match the ring buffer's.
+    /// Be aware that it is not safe to have this being written to from multiple threads.
+    /// This is part of a **single producer** single consumer ring buffer.
+    pub fn write(&self, src: &Frames) -> usize {
+        assert!(
+            src.format() == self.format() && src.channels() == self.channels(),
+            "frames and ring buffer format did not match (frames: {:?}x{}, ring buffer: {:?}x{})",
+            src.format(),
+            src.channels(),
+            self.format(),
+            self.channels()
+        );
+
+        self.inner.write(src.frame_count(), |dest| {
+            let byte_count = dest.byte_count();
+            dest.as_bytes_mut()
+                .copy_from_slice(&src.as_bytes()[0..byte_count]);
+        })
+    }
+
+    /// Used to retrieve a section of the ring buffer for writing. You specify the number of frames
+    /// you would like to write to and frames with the number of requested frames (or less if the
+    /// buffer needs to wrap), will be passed to the given closure.
+    pub fn write_with<F>(&self, frame_count_requested: usize, f: F) -> usize
+    where
+        F: FnOnce(&mut FramesMut),
+    {
+        self.inner.write(frame_count_requested, f)
+    }
+
+    /// Returns the number of frames that are available for writing.
+    pub fn available(&self) -> usize {
+        self.inner.available_write()
+    }
+
+    /// The format of the frames in the ring buffer.
+    pub fn format(&self) -> Format {
+        self.inner.format()
+    }
+
+    /// The number of channels in each frame of the ring buffer.
+    pub fn channels(&self) -> u32 {
+        self.inner.channels()
+    }
+}
+
+impl Clone for PcmRingBufferSend {
+    fn clone(&self) -> Self {
+        PcmRingBufferSend {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+/// Be aware that it is not safe to have this being written to from multiple threads.
+/// This is part of a single producer **single consumer** ring buffer.
+pub struct PcmRingBufferRecv {
+    inner: Arc<PcmRingBuffer>,
+}
+
+impl PcmRingBufferRecv {
+    /// Read frames from the ring buffer, returning the number of frames that were successfully
+    /// read. The format and channel count of `dest` must match the ring buffer's.
+    /// Be aware that it is not safe to have this being written to from multiple threads.
+    /// This is part of a single producer **single consumer** ring buffer.
+    pub fn read(&self, dest: &mut FramesMut) -> usize {
+        assert!(
+            dest.format() == self.format() && dest.channels() == self.channels(),
+            "frames and ring buffer format did not match (frames: {:?}x{}, ring buffer: {:?}x{})",
+            dest.format(),
+            dest.channels(),
+            self.format(),
+            self.channels()
+        );
+
+        self.inner.read(dest.frame_count(), |src| {
+            let byte_count = src.byte_count();
+            dest.as_bytes_mut()[0..byte_count].copy_from_slice(src.as_bytes());
+        })
+    }
+
+    /// Used to retrieve a section of the ring buffer for reading. You specify the number of frames
+    /// you would like to read and frames with the number of requested frames (or less if the
+    /// buffer needs to wrap), will be passed to the given closure.
+    pub fn read_with<F>(&self, frame_count_requested: usize, f: F) -> usize
+    where
+        F: FnOnce(&Frames),
+    {
+        self.inner.read(frame_count_requested, f)
+    }
+
+    /// Returns the number of frames that are available for reading.
+    pub fn available(&self) -> usize {
+        self.inner.available_read()
+    }
+
+    /// The format of the frames in the ring buffer.
+    pub fn format(&self) -> Format {
+        self.inner.format()
+    }
+
+    /// The number of channels in each frame of the ring buffer.
+    pub fn channels(&self) -> u32 {
+        self.inner.channels()
+    }
+}
+
+impl Clone for PcmRingBufferRecv {
+    fn clone(&self) -> Self {
+        PcmRingBufferRecv {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+/// Create a sender/receiver pair for a single producer single consumer ring buffer of PCM frames.
+/// `buffer_size_in_frames` is the number of frames of the given format and channel count that the
+/// ring buffer can hold.
+pub fn pcm_ring_buffer(
+    format: Format,
+    channels: u32,
+    buffer_size_in_frames: usize,
+) -> Result<(PcmRingBufferSend, PcmRingBufferRecv), Error> {
+    PcmRingBuffer::create_pair(format, channels, buffer_size_in_frames)
+}
+
+/// A PCM ring buffer for passing frames from a capture device to a playback device. It is sized
+/// from the capture side's period size and starts out with a couple of periods of silence queued
+/// up so that small differences in period sizes and timing between the two sides are absorbed.
+///
+/// This does not perform any conversion, so both sides are expected to use the same format, channel
+/// count and sample rate.
+pub(crate) struct DuplexRingBuffer {
+    inner: sys::ma_duplex_rb,
+    underruns: AtomicU64,
+    overruns: AtomicU64,
+}
+
+impl DuplexRingBuffer {
+    pub(crate) fn split(self) -> (DuplexRingBufferSend, DuplexRingBufferRecv) {
+        let wrapped = Arc::new(self);
+        let recv = DuplexRingBufferRecv {
+            inner: Arc::clone(&wrapped),
+        };
+        let send = DuplexRingBufferSend { inner: wrapped };
+        (send, recv)
+    }
+
+    pub(crate) fn new(
+        format: Format,
+        channels: u32,
+        sample_rate: u32,
+        capture_sample_rate: u32,
+        capture_period_size_in_frames: u32,
+    ) -> Result<DuplexRingBuffer, Error> {
+        let mut ring_buffer = std::mem::MaybeUninit::<sys::ma_duplex_rb>::uninit();
+
+        unsafe {
+            let result = sys::ma_duplex_rb_init(
+                sample_rate,
+                format as _,
+                channels,
+                capture_sample_rate,
+                capture_period_size_in_frames,
+                std::ptr::null(),
+                ring_buffer.as_mut_ptr(),
+            );
+
+            map_result!(
+                result,
+                DuplexRingBuffer {
+                    inner: ring_buffer.assume_init(),
+                    underruns: AtomicU64::new(0),
+                    overruns: AtomicU64::new(0),
+                }
+            )
+        }
+    }
+
+    #[inline]
+    pub(crate) fn rb(&self) -> &PcmRingBuffer {
+        unsafe { &*(&self.inner.rb as *const sys::ma_pcm_rb as *const PcmRingBuffer) }
+    }
+
+    /// Writes as many frames from `src` as will fit, wrapping around the end of the buffer if
+    /// required. Returns the number of frames that were written.
+    fn write_all(&self, src: &Frames) -> usize {
+        let bytes_per_frame = self.rb().bytes_per_frame();
+        let frame_count = src.frame_count();
+        let mut written = 0;
+
+        while written < frame_count {
+            let src_bytes = &src.as_bytes()[(written * bytes_per_frame)..];
+            let count = self.rb().write(frame_count - written, |dest| {
+                let byte_count = dest.byte_count();
+                dest.as_bytes_mut()
+                    .copy_from_slice(&src_bytes[0..byte_count]);
+            });
+
+            if count == 0 {
+                break;
+            }
+            written += count;
+        }
+
+        written
+    }
+
+    /// Reads as many frames into `dest` as are available, wrapping around the end of the buffer if
+    /// required. Returns the number of frames that were read.
+    fn read_all(&self, dest: &mut FramesMut) -> usize {
+        let bytes_per_frame = self.rb().bytes_per_frame();
+        let frame_count = dest.frame_count();
+        let mut read = 0;
+
+        while read < frame_count {
+            let dest_bytes = &mut dest.as_bytes_mut()[(read * bytes_per_frame)..];
+            let count = self.rb().read(frame_count - read, |src| {
+                let byte_count = src.byte_count();
+                dest_bytes[0..byte_count].copy_from_slice(src.as_bytes());
+            });
+
+            if count == 0 {
+                break;
+            }
+            read += count;
+        }
+
+        read
+    }
+}
+
+impl Drop for DuplexRingBuffer {
+    fn drop(&mut self) {
+        unsafe { sys::ma_duplex_rb_uninit(&mut self.inner) };
+    }
+}
+
+unsafe impl Send for DuplexRingBuffer {}
+unsafe impl Sync for DuplexRingBuffer {}
+
+/// The capture side of a duplex ring buffer. This should be written to from the data callback of
+/// the capture device.
+///
+/// Be aware that it is not safe to have this being written to from multiple threads.
+/// This is part of a **single producer** single consumer ring buffer.
+pub struct DuplexRingBufferSend {
+    inner: Arc<DuplexRingBuffer>,
+}
+
+impl DuplexRingBufferSend {
+    /// Write captured frames into the ring buffer, returning the number of frames that were
+    /// written. If there is not enough room for all of the frames the remaining frames are dropped
+    /// and an overrun is recorded. The format and channel count of `src` must match the ring
+    /// buffer's.
+    pub fn write(&self, src: &Frames) -> usize {
+        assert!(
+            src.format() == self.format() && src.channels() == self.channels(),
+            "frames and ring buffer format did not match (frames: {:?}x{}, ring buffer: {:?}x{})",
+            src.format(),
+            src.channels(),
+            self.format(),
+            self.channels()
+        );
+
+        let written = self.inner.write_all(src);
+        if written < src.frame_count() {
+            self.inner.overruns.fetch_add(1, Ordering::Relaxed);
+        }
+        written
+    }
+
+    /// Returns the number of frames that are available for writing.
+    pub fn available(&self) -> usize {
+        self.inner.rb().available_write()
+    }
+
+    /// The number of times that a write could not fit all of its frames into the ring buffer.
+    pub fn overruns(&self) -> u64 {
+        self.inner.overruns.load(Ordering::Relaxed)
+    }
+
+    /// The number of times that a read could not be completely filled from the ring buffer.
+    pub fn underruns(&self) -> u64 {
+        self.inner.underruns.load(Ordering::Relaxed)
+    }
+
+    /// The format of the frames in the ring buffer.
+    pub fn format(&self) -> Format {
+        self.inner.rb().format()
+    }
+
+    /// The number of channels in each frame of the ring buffer.
+    pub fn channels(&self) -> u32 {
+        self.inner.rb().channels()
+    }
+}
+
+/// The playback side of a duplex ring buffer. This should be read from in the data callback of the
+/// playback device.
+///
+/// Be aware that it is not safe to have this being read from multiple threads.
+/// This is part of a single producer **single consumer** ring buffer.
+pub struct DuplexRingBufferRecv {
+    inner: Arc<DuplexRingBuffer>,
+}
+
+impl DuplexRingBufferRecv {
+    /// Read frames from the ring buffer into `dest`, returning the number of frames that were
+    /// read. If there are not enough frames available the rest of `dest` is filled with silence and
+    /// an underrun is recorded. The format and channel count of `dest` must match the ring
+    /// buffer's.
+    pub fn read(&self, dest: &mut FramesMut) -> usize {
+        assert!(
+            dest.format() == self.format() && dest.channels() == self.channels(),
+            "frames and ring buffer format did not match (frames: {:?}x{}, ring buffer: {:?}x{})",
+            dest.format(),
+            dest.channels(),
+            self.format(),
+            self.channels()
+        );
+
+        let read = self.inner.read_all(dest);
+        let frame_count = dest.frame_count();
+
+        if read < frame_count {
+            self.inner.underruns.fetch_add(1, Ordering::Relaxed);
+
+            unsafe {
+                let offset = read * self.inner.rb().bytes_per_frame();
+                sys::ma_silence_pcm_frames(
+                    dest.as_mut_ptr().add(offset).cast(),
+                    (frame_count - read) as u64,
+                    self.format() as _,
+                    self.channels(),
+                );
+            }
+        }
+
+        read
+    }
+
+    /// Returns the number of frames that are available for reading.
+    pub fn available(&self) -> usize {
+        self.inner.rb().available_read()
+    }
+
+    /// The number of times that a write could not fit all of its frames into the ring buffer.
+    pub fn overruns(&self) -> u64 {
+        self.inner.overruns.load(Ordering::Relaxed)
+    }
+
+    /// The number of times that a read could not be completely filled from the ring buffer.
+    pub fn underruns(&self) -> u64 {
+        self.inner.underruns.load(Ordering::Relaxed)
+    }
+
+    /// The format of the frames in the ring buffer.
+    pub fn format(&self) -> Format {
+        self.inner.rb().format()
+    }
+
+    /// The number of channels in each frame of the ring buffer.
+    pub fn channels(&self) -> u32 {
+        self.inner.rb().channels()
+    }
+}
+
+/// Create a sender/receiver pair for passing frames from a capture device to a playback device.
+/// The ring buffer holds frames of the given `format`, `channels` and `sample_rate` and is sized
+/// to absorb a few capture periods of `capture_period_size_in_frames` frames at
+/// `capture_sample_rate`.
+pub fn duplex_ring_buffer(
+    format: Format,
+    channels: u32,
+    sample_rate: u32,
+    capture_sample_rate: u32,
+    capture_period_size_in_frames: u32,
+) -> Result<(DuplexRingBufferSend, DuplexRingBufferRecv), Error> {
+    DuplexRingBuffer::new(
+        format,
+        channels,
+        sample_rate,
+        capture_sample_rate,
+        capture_period_size_in_frames,
+    )
+    .map(DuplexRingBuffer::split)
+}
+
+/// Create a duplex ring buffer for an initialized capture (or duplex) device. The ring buffer uses
+/// the device's capture format, channel count and sample rate, and is sized from the device's
+/// actual capture period size. The playback device should be configured to use the same format,
+/// channel count and sample rate.
+pub fn duplex_ring_buffer_for_device(
+    device: &RawDevice,
+) -> Result<(DuplexRingBufferSend, DuplexRingBufferRecv), Error> {
+    let capture = device.capture();
+    duplex_ring_buffer(
+        capture.format(),
+        capture.channels(),
+        device.sample_rate(),
+        capture.internal_sample_rate(),
+        capture.internal_period_size_in_frames(),
+    )
+}
+
+/// Create a duplex ring buffer from a capture (or duplex) device config. The config must have an
+/// explicit capture format, channel count, sample rate and period size (either in frames or in
+/// milliseconds), otherwise this will fail with `Error::InvalidArgs`. If the device is going to use
+/// defaults for any of these, create the ring buffer with `duplex_ring_buffer_for_device` after the
+/// device has been initialized instead.
+pub fn duplex_ring_buffer_for_config(
+    config: &DeviceConfig,
+) -> Result<(DuplexRingBufferSend, DuplexRingBufferRecv), Error> {
+    let capture = config.capture();
+    let sample_rate = config.sample_rate();
+    let period_size_in_frames = if config.period_size_in_frames() != 0 {
+        config.period_size_in_frames()
+    } else {
+        (sample_rate as u64 * config.period_size_in_milliseconds() as u64 / 1000) as u32
+    };
+
+    if capture.format() == Format::Unknown
+        || capture.channels() == 0
+        || sample_rate == 0
+        || period_size_in_frames == 0
+    {
+        return Err(Error::InvalidArgs);
+    }
+
+    duplex_ring_buffer(
+        capture.format(),
+        capture.channels(),
+        sample_rate,
+        sample_rate,
+        period_size_in_frames,
+    )
+}
@@ -0,0 +1,435 @@
+use crate::base::*;
+use miniaudio_sys as sys;
+use std::ffi::{CStr, CString};
+use std::io;
+use std::mem::MaybeUninit;
+use std::os::raw::{c_char, c_void};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::Arc;
+
+bitflags::bitflags! {
+    /// How a file should be opened by a virtual file system.
+    #[repr(transparent)]
+    pub struct OpenMode: u32 {
+        const READ = sys::MA_OPEN_MODE_READ;
+        const WRITE = sys::MA_OPEN_MODE_WRITE;
+    }
+}
+
+/// A virtual file system that miniaudio can open files through. This can be implemented over asset
+/// packs, archives, in-memory maps or anything else that can hand out files by path.
+pub trait Vfs: Send + Sync {
+    /// Opens the file at `path`.
+    fn open(&self, path: &str, mode: OpenMode) -> io::Result<Box<dyn VfsFile>>;
+}
+
+/// A file that has been opened by a `Vfs`. Only reading and seeking are required; files that can
+/// be written to should also implement `write`.
+pub trait VfsFile: Send {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize>;
+
+    fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+        Err(io::ErrorKind::PermissionDenied.into())
+    }
+
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64>;
+
+    /// The size of the file in bytes. By default this seeks to the end of the file and back.
+    fn size(&mut self) -> io::Result<u64> {
+        let position = self.seek(io::SeekFrom::Current(0))?;
+        let size = self.seek(io::SeekFrom::End(0))?;
+        self.seek(io::SeekFrom::Start(position))?;
+        Ok(size)
+    }
+}
+
+impl VfsFile for std::fs::File {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        io::Read::read(self, buf)
+    }
+
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        io::Write::write(self, buf)
+    }
+
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        io::Seek::seek(self, pos)
+    }
+
+    fn size(&mut self) -> io::Result<u64> {
+        self.metadata().map(|metadata| metadata.len())
+    }
+}
+
+impl<T: AsRef<[u8]> + Send> VfsFile for io::Cursor<T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        io::Read::read(self, buf)
+    }
+
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        io::Seek::seek(self, pos)
+    }
+
+    fn size(&mut self) -> io::Result<u64> {
+        Ok(self.get_ref().as_ref().len() as u64)
+    }
+}
+
+/// A shared handle to a virtual file system that can be passed to miniaudio. Cloning this will
+/// simply return another reference to the same file system.
+#[derive(Clone)]
+pub struct VfsHandle(Arc<RawVfs>);
+
+impl VfsHandle {
+    /// Creates a handle for a virtual file system implemented in Rust.
+    pub fn new<V: 'static + Vfs>(vfs: V) -> VfsHandle {
+        Self::from_boxed(Box::new(vfs))
+    }
+
+    pub fn from_boxed(vfs: Box<dyn Vfs>) -> VfsHandle {
+        let callbacks = sys::ma_vfs_callbacks {
+            onOpen: Some(vfs_open),
+            onOpenW: None,
+            onClose: Some(vfs_close),
+            onRead: Some(vfs_read),
+            onWrite: Some(vfs_write),
+            onSeek: Some(vfs_seek),
+            onTell: Some(vfs_tell),
+            onInfo: Some(vfs_info),
+        };
+
+        VfsHandle(Arc::new(RawVfs::Rust(RustVfs { callbacks, vfs })))
+    }
+
+    /// Creates a handle for miniaudio's default file system, which opens files using stdio.
+    pub fn default_vfs() -> Result<VfsHandle, Error> {
+        let mut vfs = MaybeUninit::<sys::ma_default_vfs>::uninit();
+        let result = unsafe { sys::ma_default_vfs_init(vfs.as_mut_ptr(), std::ptr::null()) };
+        map_result!(
+            result,
+            VfsHandle(Arc::new(RawVfs::Default(unsafe { vfs.assume_init() })))
+        )
+    }
+
+    /// Opens a file through this file system. The returned file implements `io::Read`, `io::Write`
+    /// and `io::Seek` so it can also be passed to `Encoder::from_write` or `Decoder::from_read`.
+    pub fn open(&self, path: &str, mode: OpenMode) -> Result<VfsFileHandle, Error> {
+        let path = CString::new(path).map_err(|_err| Error::InvalidFile)?;
+        let mut file: sys::ma_vfs_file = std::ptr::null_mut();
+
+        let result =
+            unsafe { sys::ma_vfs_open(self.as_raw_vfs(), path.as_ptr(), mode.bits(), &mut file) };
+
+        map_result!(
+            result,
+            VfsFileHandle {
+                vfs: self.clone(),
+                file,
+            }
+        )
+    }
+
+    #[inline]
+    pub(crate) fn as_raw_vfs(&self) -> *mut sys::ma_vfs {
+        match *self.0 {
+            RawVfs::Default(ref vfs) => vfs as *const sys::ma_default_vfs as *mut _,
+            RawVfs::Rust(ref vfs) => vfs as *const RustVfs as *mut _,
+        }
+    }
+}
+
+enum RawVfs {
+    Default(sys::ma_default_vfs),
+    Rust(RustVfs),
+}
+
+unsafe impl Send for RawVfs {}
+unsafe impl Sync for RawVfs {}
+
+#[repr(C)]
+struct RustVfs {
+    // NOTE: This must be the first field because miniaudio reads the callbacks through the VFS
+    // pointer.
+    callbacks: sys::ma_vfs_callbacks,
+    vfs: Box<dyn Vfs>,
+}
+
+/// A file that was opened through a `VfsHandle`. The file is closed when this is dropped.
+pub struct VfsFileHandle {
+    vfs: VfsHandle,
+    file: sys::ma_vfs_file,
+}
+
+impl VfsFileHandle {
+    /// The size of the file in bytes.
+    pub fn size(&self) -> Result<u64, Error> {
+        let mut info = MaybeUninit::<sys::ma_file_info>::uninit();
+        let result =
+            unsafe { sys::ma_vfs_info(self.vfs.as_raw_vfs(), self.file, info.as_mut_ptr()) };
+        map_result!(result, unsafe { info.assume_init().sizeInBytes })
+    }
+}
+
+impl io::Read for VfsFileHandle {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut bytes_read = 0;
+        let result = unsafe {
+            sys::ma_vfs_read(
+                self.vfs.as_raw_vfs(),
+                self.file,
+                buf.as_mut_ptr() as *mut _,
+                buf.len(),
+                &mut bytes_read,
+            )
+        };
+
+        // Reaching the end of the file is reported as MA_AT_END, which is just a short read here.
+        if result == sys::MA_AT_END {
+            Ok(bytes_read)
+        } else {
+            Error::from_c_result(result)
+                .map(|_| bytes_read)
+                .map_err(io_error_from_ma)
+        }
+    }
+}
+
+impl io::Write for VfsFileHandle {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut bytes_written = 0;
+        let result = unsafe {
+            sys::ma_vfs_write(
+                self.vfs.as_raw_vfs(),
+                self.file,
+                buf.as_ptr() as *const _,
+                buf.len(),
+                &mut bytes_written,
+            )
+        };
+
+        Error::from_c_result(result)
+            .map(|_| bytes_written)
+            .map_err(io_error_from_ma)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl io::Seek for VfsFileHandle {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        let (offset, origin) = match pos {
+            io::SeekFrom::Start(offset) => (offset as i64, sys::ma_seek_origin_start),
+            io::SeekFrom::Current(offset) => (offset, sys::ma_seek_origin_current),
+            io::SeekFrom::End(offset) => (offset, sys::ma_seek_origin_end),
+        };
+
+        let vfs = self.vfs.as_raw_vfs();
+        Error::from_c_result(unsafe { sys::ma_vfs_seek(vfs, self.file, offset, origin) })
+            .map_err(io_error_from_ma)?;
+
+        let mut cursor = 0;
+        Error::from_c_result(unsafe { sys::ma_vfs_tell(vfs, self.file, &mut cursor) })
+            .map(|_| cursor as u64)
+            .map_err(io_error_from_ma)
+    }
+}
+
+impl Drop for VfsFileHandle {
+    fn drop(&mut self) {
+        unsafe { sys::ma_vfs_close(self.vfs.as_raw_vfs(), self.file) };
+    }
+}
+
+unsafe impl Send for VfsFileHandle {}
+
+fn io_error_from_ma(err: Error) -> io::Error {
+    let kind = match err {
+        Error::DoesNotExist => io::ErrorKind::NotFound,
+        Error::AccessDenied => io::ErrorKind::PermissionDenied,
+        Error::InvalidArgs => io::ErrorKind::InvalidInput,
+        _ => io::ErrorKind::Other,
+    };
+    io::Error::new(kind, err)
+}
+
+fn io_error_to_ma(err: &io::Error) -> sys::ma_result {
+    match err.kind() {
+        io::ErrorKind::NotFound => sys::MA_DOES_NOT_EXIST,
+        io::ErrorKind::PermissionDenied => sys::MA_ACCESS_DENIED,
+        io::ErrorKind::InvalidInput => sys::MA_INVALID_ARGS,
+        io::ErrorKind::UnexpectedEof => sys::MA_AT_END,
+        _ => sys::MA_IO_ERROR,
+    }
+}
+
+// NOTE: Unwinding into C is undefined behavior so all of these report a panic as a generic error.
+
+unsafe fn file_from_handle<'f>(file: sys::ma_vfs_file) -> &'f mut Box<dyn VfsFile> {
+    &mut *(file as *mut Box<dyn VfsFile>)
+}
+
+unsafe extern "C" fn vfs_open(
+    vfs: *mut sys::ma_vfs,
+    path: *const c_char,
+    open_mode: u32,
+    file_out: *mut sys::ma_vfs_file,
+) -> sys::ma_result {
+    if vfs.is_null() || path.is_null() || file_out.is_null() {
+        return sys::MA_INVALID_ARGS;
+    }
+    *file_out = std::ptr::null_mut();
+
+    let path = match CStr::from_ptr(path).to_str() {
+        Ok(path) => path,
+        Err(_) => return sys::MA_INVALID_ARGS,
+    };
+
+    let rust_vfs = &*(vfs as *const RustVfs);
+    let mode = OpenMode::from_bits_truncate(open_mode);
+    match catch_unwind(AssertUnwindSafe(|| rust_vfs.vfs.open(path, mode))) {
+        Ok(Ok(file)) => {
+            *file_out = Box::into_raw(Box::new(file)) as sys::ma_vfs_file;
+            sys::MA_SUCCESS as _
+        }
+        Ok(Err(err)) => io_error_to_ma(&err),
+        Err(_) => sys::MA_ERROR,
+    }
+}
+
+unsafe extern "C" fn vfs_close(_vfs: *mut sys::ma_vfs, file: sys::ma_vfs_file) -> sys::ma_result {
+    if file.is_null() {
+        return sys::MA_INVALID_ARGS;
+    }
+
+    // Recreate the box and allow it to be dropped.
+    let file: Box<Box<dyn VfsFile>> = Box::from_raw(file as *mut _);
+    match catch_unwind(AssertUnwindSafe(move || drop(file))) {
+        Ok(()) => sys::MA_SUCCESS as _,
+        Err(_) => sys::MA_ERROR,
+    }
+}
+
+unsafe extern "C" fn vfs_read(
+    _vfs: *mut sys::ma_vfs,
+    file: sys::ma_vfs_file,
+    dst: *mut c_void,
+    size_in_bytes: usize,
+    bytes_read_out: *mut usize,
+) -> sys::ma_result {
+    if !bytes_read_out.is_null() {
+        *bytes_read_out = 0;
+    }
+
+    if file.is_null() || dst.is_null() {
+        return sys::MA_INVALID_ARGS;
+    }
+
+    let file = file_from_handle(file);
+    let buffer = std::slice::from_raw_parts_mut(dst as *mut u8, size_in_bytes);
+    match catch_unwind(AssertUnwindSafe(|| file.read(buffer))) {
+        Ok(Ok(bytes_read)) => {
+            if !bytes_read_out.is_null() {
+                *bytes_read_out = bytes_read;
+            }
+            sys::MA_SUCCESS as _
+        }
+        Ok(Err(err)) => io_error_to_ma(&err),
+        Err(_) => sys::MA_ERROR,
+    }
+}
+
+unsafe extern "C" fn vfs_write(
+    _vfs: *mut sys::ma_vfs,
+    file: sys::ma_vfs_file,
+    src: *const c_void,
+    size_in_bytes: usize,
+    bytes_written_out: *mut usize,
+) -> sys::ma_result {
+    if !bytes_written_out.is_null() {
+        *bytes_written_out = 0;
+    }
+
+    if file.is_null() || src.is_null() {
+        return sys::MA_INVALID_ARGS;
+    }
+
+    let file = file_from_handle(file);
+    let buffer = std::slice::from_raw_parts(src as *const u8, size_in_bytes);
+    match catch_unwind(AssertUnwindSafe(|| file.write(buffer))) {
+        Ok(Ok(bytes_written)) => {
+            if !bytes_written_out.is_null() {
+                *bytes_written_out = bytes_written;
+            }
+            sys::MA_SUCCESS as _
+        }
+        Ok(Err(err)) => io_error_to_ma(&err),
+        Err(_) => sys::MA_ERROR,
+    }
+}
+
+unsafe extern "C" fn vfs_seek(
+    _vfs: *mut sys::ma_vfs,
+    file: sys::ma_vfs_file,
+    offset: i64,
+    origin: sys::ma_seek_origin,
+) -> sys::ma_result {
+    if file.is_null() {
+        return sys::MA_INVALID_ARGS;
+    }
+
+    let pos = match origin {
+        sys::ma_seek_origin_start if offset >= 0 => io::SeekFrom::Start(offset as u64),
+        sys::ma_seek_origin_current => io::SeekFrom::Current(offset),
+        sys::ma_seek_origin_end => io::SeekFrom::End(offset),
+        _ => return sys::MA_INVALID_ARGS,
+    };
+
+    let file = file_from_handle(file);
+    match catch_unwind(AssertUnwindSafe(|| file.seek(pos))) {
+        Ok(Ok(_)) => sys::MA_SUCCESS as _,
+        Ok(Err(err)) => io_error_to_ma(&err),
+        Err(_) => sys::MA_ERROR,
+    }
+}
+
+unsafe extern "C" fn vfs_tell(
+    _vfs: *mut sys::ma_vfs,
+    file: sys::ma_vfs_file,
+    cursor_out: *mut i64,
+) -> sys::ma_result {
+    if file.is_null() || cursor_out.is_null() {
+        return sys::MA_INVALID_ARGS;
+    }
+
+    let file = file_from_handle(file);
+    match catch_unwind(AssertUnwindSafe(|| file.seek(io::SeekFrom::Current(0)))) {
+        Ok(Ok(cursor)) => {
+            *cursor_out = cursor as i64;
+            sys::MA_SUCCESS as _
+        }
+        Ok(Err(err)) => io_error_to_ma(&err),
+        Err(_) => sys::MA_ERROR,
+    }
+}
+
+unsafe extern "C" fn vfs_info(
+    _vfs: *mut sys::ma_vfs,
+    file: sys::ma_vfs_file,
+    info_out: *mut sys::ma_file_info,
+) -> sys::ma_result {
+    if file.is_null() || info_out.is_null() {
+        return sys::MA_INVALID_ARGS;
+    }
+
+    let file = file_from_handle(file);
+    match catch_unwind(AssertUnwindSafe(|| file.size())) {
+        Ok(Ok(size)) => {
+            (*info_out).sizeInBytes = size;
+            sys::MA_SUCCESS as _
+        }
+        Ok(Err(err)) => io_error_to_ma(&err),
+        Err(_) => sys::MA_ERROR,
+    }
+}
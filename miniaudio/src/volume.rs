@@ -0,0 +1,46 @@
+use miniaudio_sys as sys;
+
+/// Converts a gain in decibels to a linear volume factor, where 0 dB is a factor of 1.
+#[inline]
+pub fn gain_db_to_factor(gain_db: f32) -> f32 {
+    unsafe { sys::ma_gain_db_to_factor(gain_db) }
+}
+
+/// Converts a linear volume factor to a gain in decibels, where a factor of 1 is 0 dB.
+#[inline]
+pub fn factor_to_gain_db(factor: f32) -> f32 {
+    unsafe { sys::ma_factor_to_gain_db(factor) }
+}
+
+/// Clamps every sample to the range `[-1, 1]`.
+#[inline]
+pub fn clip_samples_f32(samples: &mut [f32]) {
+    unsafe { sys::ma_clip_samples_f32(samples.as_mut_ptr(), samples.len() as u64) };
+}
+
+/// Linearly interpolates between the samples of `a` and `b`, writing the result to `output`. A
+/// factor of 0 results in `a` and a factor of 1 results in `b`.
+///
+/// This will panic if `output`, `a` and `b` do not all have the same length.
+#[inline]
+pub fn blend_f32(output: &mut [f32], a: &[f32], b: &[f32], factor: f32) {
+    assert!(
+        output.len() == a.len() && output.len() == b.len(),
+        "blend with different sample counts (output: {}, a: {}, b: {})",
+        output.len(),
+        a.len(),
+        b.len()
+    );
+
+    // NOTE: The inputs are not marked as const in the C source even though they are never
+    // modified, so we cast them here.
+    unsafe {
+        sys::ma_blend_f32(
+            output.as_mut_ptr(),
+            a.as_ptr() as *mut _,
+            b.as_ptr() as *mut _,
+            factor,
+            output.len() as u32,
+        )
+    };
+}